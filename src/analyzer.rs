@@ -0,0 +1,921 @@
+use nih_plug::{prelude::Enum, util};
+use nih_plug_egui::egui::{Color32, TextureHandle};
+use rustfft::{num_complex::Complex, FftDirection, FftPlanner};
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, sync::Mutex};
+
+use crate::{AnalyzerView, ChannelMode, Colormap, OptimizedBuffer, ScrollscopeParams, SpectrumAveraging, ANALYZER_FREQ_LIMITS};
+
+// Main input plus the 5 aux inputs
+pub(crate) const ANALYZER_INPUTS: usize = 6;
+pub(crate) const INPUT_NAMES: [&str; ANALYZER_INPUTS] = ["Main", "Aux 1", "Aux 2", "Aux 3", "Aux 4", "Aux 5"];
+// More FFT frames than this in one editor frame (a stall, or tiny sizes at high overlap) only keep the newest
+const MAX_FRAMES_PER_UPDATE: usize = 16;
+// Linear averaging slides over this many runs of frames instead of keeping every frame in the window
+const LINEAR_SEGMENTS: usize = 8;
+// Spectrogram columns are resampled to this many rows, log spaced over ANALYZER_FREQ_LIMITS
+pub(crate) const SPECTROGRAM_ROWS: usize = 512;
+// Points per reference spectrum, log spaced over ANALYZER_FREQ_LIMITS
+pub(crate) const REFERENCE_POINTS: usize = 1024;
+// Colors handed out to new reference spectra in turn
+pub(crate) const REFERENCE_COLORS: [[u8; 3]; 6] = [
+    [200, 200, 200],
+    [255, 170, 60],
+    [120, 220, 160],
+    [170, 140, 255],
+    [255, 120, 170],
+    [90, 200, 255],
+];
+// Most columns kept, 30 s of history at the smallest FFT size and most overlap needs less than this
+const MAX_SPECTROGRAM_COLUMNS: usize = 8192;
+
+// Analyzer state kept by the editor between frames. FFT frames are taken every hop samples of
+// the input rather than every repaint, so the size and overlap set the update rate
+pub(crate) struct AnalyzerState {
+    // Where the sample ring's write head was last time we looked
+    last_write: Option<usize>,
+    // Samples written since the newest FFT frame
+    pending: usize,
+    fft_len: usize,
+    // Averaged magnitudes per input, drawn again until the next hop comes round
+    pub(crate) magnitudes: [Vec<f32>; ANALYZER_INPUTS],
+    // Peak hold trace per input, empty while peak hold is off
+    pub(crate) peaks: [Vec<f32>; ANALYZER_INPUTS],
+    ballistics: [ChannelBallistics; ANALYZER_INPUTS],
+    // Phase of the newest frame per input up to Nyquist, only kept while the phase view is up
+    pub(crate) phases: [Vec<f32>; ANALYZER_INPUTS],
+    pub(crate) transfer: TransferFunction,
+    // Samples counted since the editor opened, spectrogram columns are placed in time by it
+    clock: u64,
+    // Spectrogram history, oldest first, and the input it's of
+    pub(crate) spectrogram: VecDeque<SpectrogramColumn>,
+    spectrogram_source: usize,
+    pub(crate) spectrogram_texture: Option<TextureHandle>,
+    // Whether the reference spectra window is open
+    pub(crate) references_open: bool,
+}
+
+// One FFT frame of the spectrogram
+pub(crate) struct SpectrogramColumn {
+    // Clock at the end of the frame and the samples since the one before
+    pub(crate) time: u64,
+    pub(crate) span: usize,
+    // dBFS per row, lowest frequency first
+    pub(crate) levels: Vec<f32>,
+    // Clock at the last beat line inside the frame and whether it starts a bar
+    pub(crate) beat: Option<(u64, bool)>,
+}
+
+impl AnalyzerState {
+    pub(crate) fn new() -> Self {
+        Self {
+            last_write: None,
+            pending: 0,
+            fft_len: 0,
+            magnitudes: Default::default(),
+            peaks: Default::default(),
+            ballistics: Default::default(),
+            phases: Default::default(),
+            transfer: TransferFunction::default(),
+            clock: 0,
+            spectrogram: VecDeque::new(),
+            spectrogram_source: 0,
+            spectrogram_texture: None,
+            references_open: false,
+        }
+    }
+
+    // Runs the FFT frames that came due since the last editor frame through the analyzer, returns the
+    // FFT size in use. Between hops the last spectra are drawn again. Buffers are the left and right
+    // sample rings, both are read up to stereo_write_position()
+    pub(crate) fn update(&mut self, buffers: [&OptimizedBuffer; 2], params: &ScrollscopeParams, fft: &Mutex<FftPlanner<f32>>, sample_rate: f32) -> usize {
+        let buffer = buffers[0];
+        // The FFT size is its own setting, capped at what the ring holds at low sample rates
+        let fft_len: usize = params.fft_size.value().len().min(buffer.capacity());
+        let hop: usize = params.fft_overlap.value().hop(fft_len);
+        let write = stereo_write_position(buffers);
+        let frames = self.due_frames(write, buffer.capacity(), fft_len, hop);
+        if frames.is_empty() {
+            return fft_len;
+        }
+        let window: Vec<f32> = params.fft_window.value().coefficients(fft_len);
+        let ballistics = Ballistics {
+            averaging: params.spectrum_averaging.value(),
+            average_time: params.average_time.value() / 1000.0,
+            peak_hold: params.peak_hold.value(),
+            hold_time: params.peak_hold_time.value() / 1000.0,
+            release: params.peak_release.value(),
+            frame_time: hop as f32 / sample_rate,
+        };
+        // The phase difference is the phase of the transfer function's cross spectrum
+        let view = params.analyzer_view.value();
+        let transfer = view == AnalyzerView::Transfer || (view == AnalyzerView::Phase && params.phase_difference.value());
+        let reference = params.transfer_reference.value().to_index();
+        let modes = params.channel_modes().map(|mode| mode.value());
+        for behind in frames {
+            let end = buffer.position_before(write, behind);
+            let spectra: Vec<Vec<Complex<f32>>> = (0..ANALYZER_INPUTS)
+                .map(|channel| complex_spectrum(buffers, channel, modes[channel], fft_len, end, &window, fft))
+                .collect();
+            for (channel, spectrum) in spectra.iter().enumerate() {
+                let magnitudes: Vec<f32> = spectrum.iter().map(|bin| bin.norm()).collect();
+                self.add_frame(channel, &magnitudes, &ballistics);
+            }
+            if transfer {
+                self.transfer.add_frame(reference, &spectra[reference], &spectra[0], &ballistics);
+            }
+            if view == AnalyzerView::Phase {
+                for (phases, spectrum) in self.phases.iter_mut().zip(&spectra) {
+                    *phases = spectrum[..spectrum.len() / 2].iter().map(|bin| bin.arg()).collect();
+                }
+            }
+        }
+        fft_len
+    }
+
+    pub(crate) fn clock(&self) -> u64 {
+        self.clock
+    }
+
+    // Adds a frame of source to the spectrogram, dropping what's older than history samples
+    pub(crate) fn add_column(&mut self, source: usize, column: SpectrogramColumn, history: u64) {
+        if source != self.spectrogram_source {
+            self.spectrogram_source = source;
+            self.spectrogram.clear();
+        }
+        self.spectrogram.push_back(column);
+        while let Some(oldest) = self.spectrogram.front() {
+            if self.clock.saturating_sub(oldest.time) > history || self.spectrogram.len() > MAX_SPECTROGRAM_COLUMNS {
+                self.spectrogram.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    // Runs one FFT frame of an input through the averaging and peak hold
+    fn add_frame(&mut self, channel: usize, magnitudes: &[f32], settings: &Ballistics) {
+        let ballistics = &mut self.ballistics[channel];
+        self.magnitudes[channel] = ballistics.average(magnitudes, settings);
+        if settings.peak_hold {
+            ballistics.hold_peaks(magnitudes, settings);
+            self.peaks[channel].clone_from(&ballistics.peak);
+        } else {
+            ballistics.peak.clear();
+            self.peaks[channel].clear();
+        }
+    }
+
+    // How far behind the write head each FFT frame due now ends, oldest first
+    pub(crate) fn due_frames(&mut self, write: usize, capacity: usize, fft_len: usize, hop: usize) -> Vec<usize> {
+        let written = match self.last_write {
+            Some(last) if capacity > 0 => (write + capacity - last % capacity) % capacity,
+            _ => 0,
+        };
+        self.last_write = Some(write);
+        self.pending += written;
+        self.clock += written as u64;
+
+        // A new size starts over with a frame straight away
+        if fft_len != self.fft_len {
+            self.fft_len = fft_len;
+            self.pending = 0;
+            return vec![0];
+        }
+        if self.pending < hop {
+            return Vec::new();
+        }
+        let count = (self.pending / hop).min(MAX_FRAMES_PER_UPDATE);
+        self.pending %= hop;
+        (0..count).rev().map(|frame| self.pending + frame * hop).collect()
+    }
+}
+
+// How successive FFT frames are smoothed, times are in seconds
+struct Ballistics {
+    averaging: SpectrumAveraging,
+    average_time: f32,
+    peak_hold: bool,
+    hold_time: f32,
+    // dB per second once the hold time is up, 0 keeps peaks until peak hold is switched off
+    release: f32,
+    // Time between FFT frames, one hop of input
+    frame_time: f32,
+}
+
+// Averaging runs on power so it reads like an RMS average, peaks are held as magnitudes
+#[derive(Default)]
+struct ChannelBallistics {
+    averaging: Option<SpectrumAveraging>,
+    exponential: Vec<f32>,
+    // Power sums of consecutive runs of frames with their frame counts, oldest first
+    segments: VecDeque<(Vec<f32>, usize)>,
+    peak: Vec<f32>,
+    // Seconds since each bin's peak was set
+    peak_age: Vec<f32>,
+}
+
+impl ChannelBallistics {
+    fn average(&mut self, magnitudes: &[f32], settings: &Ballistics) -> Vec<f32> {
+        // Start over when the mode or the FFT size changes
+        if self.averaging != Some(settings.averaging) || self.exponential.len() != magnitudes.len() {
+            self.averaging = Some(settings.averaging);
+            self.exponential = magnitudes.iter().map(|m| m * m).collect();
+            self.segments.clear();
+        }
+
+        match settings.averaging {
+            SpectrumAveraging::Off => magnitudes.to_vec(),
+            SpectrumAveraging::Exponential => {
+                let coefficient = 1.0 - (-settings.frame_time / settings.average_time.max(f32::EPSILON)).exp();
+                for (average, magnitude) in self.exponential.iter_mut().zip(magnitudes) {
+                    *average += coefficient * (magnitude * magnitude - *average);
+                }
+                self.exponential.iter().map(|power| power.sqrt()).collect()
+            }
+            SpectrumAveraging::Linear => {
+                let window_frames = ((settings.average_time / settings.frame_time).round() as usize).max(1);
+                let segment_frames = window_frames.div_ceil(LINEAR_SEGMENTS);
+                if !matches!(self.segments.back(), Some((_, frames)) if *frames < segment_frames) {
+                    self.segments.push_back((vec![0.0; magnitudes.len()], 0));
+                }
+                if let Some((sum, frames)) = self.segments.back_mut() {
+                    for (power, magnitude) in sum.iter_mut().zip(magnitudes) {
+                        *power += magnitude * magnitude;
+                    }
+                    *frames += 1;
+                }
+                // Drop the oldest run once the rest still cover the window
+                let mut total: usize = self.segments.iter().map(|(_, frames)| frames).sum();
+                while self.segments.len() > 1 && total - self.segments[0].1 >= window_frames {
+                    total -= self.segments[0].1;
+                    self.segments.pop_front();
+                }
+                (0..magnitudes.len())
+                    .map(|bin| (self.segments.iter().map(|(sum, _)| sum[bin]).sum::<f32>() / total as f32).sqrt())
+                    .collect()
+            }
+        }
+    }
+
+    fn hold_peaks(&mut self, magnitudes: &[f32], settings: &Ballistics) {
+        if self.peak.len() != magnitudes.len() {
+            self.peak = magnitudes.to_vec();
+            self.peak_age = vec![0.0; magnitudes.len()];
+            return;
+        }
+        let release = util::db_to_gain(-settings.release * settings.frame_time);
+        for ((peak, age), magnitude) in self.peak.iter_mut().zip(self.peak_age.iter_mut()).zip(magnitudes) {
+            if *magnitude >= *peak {
+                *peak = *magnitude;
+                *age = 0.0;
+            } else {
+                *age += settings.frame_time;
+                if *age > settings.hold_time {
+                    *peak = (*peak * release).max(*magnitude);
+                }
+            }
+        }
+    }
+}
+
+// Where the left and right sample rings both have everything written. process() writes a whole
+// block to the left ring before the right one, so this is the right ring's write head
+pub(crate) fn stereo_write_position(buffers: [&OptimizedBuffer; 2]) -> usize {
+    buffers[1].write_position()
+}
+
+// Windowed spectrum of one channel, window comes from FftWindow::coefficients(). Scaled so a full
+// scale sine reads 1.0 (0 dBFS) at any FFT size. Buffers are the left and right sample rings, mode
+// picks what of the two the spectrum is of. The frame ends at the ring position end in both rings,
+// one from stereo_write_position() so a mix never pairs samples a block apart
+pub(crate) fn complex_spectrum(
+    buffers: [&OptimizedBuffer; 2],
+    channel: usize,
+    mode: ChannelMode,
+    fft_len: usize,
+    end: usize,
+    window: &[f32],
+    fft: &Mutex<FftPlanner<f32>>,
+) -> Vec<Complex<f32>> {
+    let [left, right] = buffers;
+    let mut samples: Vec<Complex<f32>> = match mode {
+        ChannelMode::Left => left.get_complex_samples_with_length(channel, fft_len, end),
+        ChannelMode::Right => right.get_complex_samples_with_length(channel, fft_len, end),
+        _ => left
+            .get_complex_samples_with_length(channel, fft_len, end)
+            .into_iter()
+            .zip(right.get_complex_samples_with_length(channel, fft_len, end))
+            .map(|(left, right)| Complex::new(mode.mix(left.re, right.re), 0.0))
+            .collect(),
+    };
+    for (sample, weight) in samples.iter_mut().zip(window) {
+        *sample *= *weight;
+    }
+    let fft_plan = fft.lock().unwrap().plan_fft(samples.len(), FftDirection::Forward);
+    fft_plan.process(&mut samples);
+    // A sine's energy splits between the positive and negative frequency bins
+    let scale = 2.0 / samples.len().max(1) as f32;
+    for bin in samples.iter_mut() {
+        *bin *= scale;
+    }
+    samples
+}
+
+pub(crate) fn magnitude_spectrum(
+    buffers: [&OptimizedBuffer; 2],
+    channel: usize,
+    mode: ChannelMode,
+    fft_len: usize,
+    end: usize,
+    window: &[f32],
+    fft: &Mutex<FftPlanner<f32>>,
+) -> Vec<f32> {
+    complex_spectrum(buffers, channel, mode, fft_len, end, window, fft).iter().map(|c| c.norm()).collect()
+}
+
+// H1 estimate of whatever sits between a reference input and main. The cross and auto spectra are
+// averaged with the analyzer's averaging time and only divided out when drawn, averaging the ratio
+// itself would let noise through
+#[derive(Default)]
+pub(crate) struct TransferFunction {
+    reference: usize,
+    // Reference to main cross spectrum and the power spectra of each, up to Nyquist
+    cross: Vec<Complex<f32>>,
+    reference_power: Vec<f32>,
+    measured_power: Vec<f32>,
+}
+
+// Response at one bin, gain as a ratio and phase in radians
+#[derive(Clone, Copy)]
+pub(crate) struct TransferPoint {
+    pub(crate) gain: f32,
+    pub(crate) phase: f32,
+    // 0 to 1, how much of main is explained by the reference at this frequency
+    pub(crate) coherence: f32,
+}
+
+impl TransferFunction {
+    fn add_frame(&mut self, reference_index: usize, reference: &[Complex<f32>], measured: &[Complex<f32>], settings: &Ballistics) {
+        let bins = reference.len() / 2;
+        // A new reference or size starts the averages over
+        let mut coefficient = 1.0 - (-settings.frame_time / settings.average_time.max(f32::EPSILON)).exp();
+        if reference_index != self.reference || self.cross.len() != bins {
+            self.reference = reference_index;
+            self.cross = vec![Complex::new(0.0, 0.0); bins];
+            self.reference_power = vec![0.0; bins];
+            self.measured_power = vec![0.0; bins];
+            coefficient = 1.0;
+        }
+        for (bin, (x, y)) in reference.iter().zip(measured).take(bins).enumerate() {
+            self.cross[bin] += (x.conj() * y - self.cross[bin]) * coefficient;
+            self.reference_power[bin] += (x.norm_sqr() - self.reference_power[bin]) * coefficient;
+            self.measured_power[bin] += (y.norm_sqr() - self.measured_power[bin]) * coefficient;
+        }
+    }
+
+    // The response at each bin up to Nyquist, None where the reference is too quiet to measure with
+    pub(crate) fn response(&self) -> Vec<Option<TransferPoint>> {
+        // -120 dBFS of reference
+        const FLOOR: f32 = 1.0e-12;
+        self.cross
+            .iter()
+            .zip(self.reference_power.iter().zip(&self.measured_power))
+            .map(|(cross, (reference_power, measured_power))| {
+                if *reference_power < FLOOR {
+                    return None;
+                }
+                let h1 = *cross / *reference_power;
+                Some(TransferPoint {
+                    gain: h1.norm(),
+                    phase: h1.arg(),
+                    coherence: (cross.norm_sqr() / (reference_power * measured_power.max(FLOOR))).min(1.0),
+                })
+            })
+            .collect()
+    }
+}
+
+// Group delay in seconds at a bin, minus the slope of the phase across the bins either side
+pub(crate) fn group_delay(response: &[Option<TransferPoint>], bin: usize, bin_hz: f32) -> Option<f32> {
+    let below = response.get(bin.checked_sub(1)?)?.as_ref()?;
+    let above = response.get(bin + 1)?.as_ref()?;
+    let step = (above.phase - below.phase + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI;
+    Some(-step / (std::f32::consts::TAU * 2.0 * bin_hz))
+}
+
+// Unwraps phase across frequency. Bins that are None are stepped over, the next one carries on from
+// the last bin that wasn't
+pub(crate) fn unwrap_phase(phases: &[Option<f32>]) -> Vec<Option<f32>> {
+    let mut offset = 0.0;
+    let mut last: Option<f32> = None;
+    phases
+        .iter()
+        .map(|phase| {
+            let phase = (*phase)?;
+            if let Some(last) = last {
+                offset -= std::f32::consts::TAU * ((phase - last) / std::f32::consts::TAU).round();
+            }
+            last = Some(phase);
+            Some(phase + offset)
+        })
+        .collect()
+}
+
+// Frequency at a row of rows log spaced over ANALYZER_FREQ_LIMITS, fractional rows land in between
+pub(crate) fn log_frequency(row: f32, rows: usize) -> f32 {
+    let (low, high) = (ANALYZER_FREQ_LIMITS.0.log10(), ANALYZER_FREQ_LIMITS.1.log10());
+    10.0f32.powf(low + (high - low) * row / rows as f32)
+}
+
+// Resamples a magnitude spectrum to rows log spaced rows in dBFS, for the spectrogram and reference
+// spectra. Rows wider than a bin take the loudest bin in them so narrow tones don't vanish up high
+pub(crate) fn log_levels(magnitudes: &[f32], bin_hz: f32, rows: usize) -> Vec<f32> {
+    let row_freq = |row: f32| log_frequency(row, rows);
+    let usable = magnitudes.len() / 2;
+    (0..rows)
+        .map(|row| {
+            // Nothing above Nyquist
+            if row_freq(row as f32) / bin_hz >= usable as f32 {
+                return util::MINUS_INFINITY_DB;
+            }
+            let first = (row_freq(row as f32) / bin_hz).ceil() as usize;
+            let last = ((row_freq(row as f32 + 1.0) / bin_hz) as usize).min(usable.saturating_sub(1));
+            let magnitude = if first <= last {
+                magnitudes[first..=last].iter().fold(0.0, |loudest: f32, m| loudest.max(*m))
+            } else {
+                magnitude_at(&magnitudes[..usable], row_freq(row as f32 + 0.5) / bin_hz)
+            };
+            util::gain_to_db(magnitude)
+        })
+        .collect()
+}
+
+// Spectrum frozen by a snapshot and drawn behind the live analyzer, saved with the plugin state.
+// Levels are kept on a log frequency grid so they don't depend on the FFT size or sample rate
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct ReferenceSpectrum {
+    pub(crate) name: String,
+    pub(crate) color: [u8; 3],
+    pub(crate) visible: bool,
+    // dBFS before tilt at REFERENCE_POINTS points from log_frequency()
+    pub(crate) levels: Vec<f32>,
+}
+
+impl ReferenceSpectrum {
+    pub(crate) fn new(name: String, color: [u8; 3], magnitudes: &[f32], bin_hz: f32) -> Self {
+        Self {
+            name,
+            color,
+            visible: true,
+            levels: log_levels(magnitudes, bin_hz, REFERENCE_POINTS),
+        }
+    }
+
+    // Level at a frequency, interpolated between points. None outside what was captured
+    pub(crate) fn level_at(&self, freq: f32) -> Option<f32> {
+        let (low, high) = (ANALYZER_FREQ_LIMITS.0.log10(), ANALYZER_FREQ_LIMITS.1.log10());
+        // Points sit in the middle of their rows
+        let position = (freq.log10() - low) / (high - low) * self.levels.len() as f32 - 0.5;
+        if !(0.0..=(self.levels.len() as f32 - 1.0)).contains(&position) {
+            return None;
+        }
+        let below = self.levels[position.floor() as usize];
+        let above = self.levels[position.ceil() as usize];
+        if below <= util::MINUS_INFINITY_DB || above <= util::MINUS_INFINITY_DB {
+            return None;
+        }
+        Some(below + (above - below) * position.fract())
+    }
+}
+
+// Spectrogram colors for levels from the bottom of the dB range to the top, interpolated between the
+// colormap's stops
+pub(crate) fn colormap_palette(colormap: Colormap, steps: usize) -> Vec<Color32> {
+    let stops = colormap.stops();
+    (0..steps)
+        .map(|step| {
+            let position = step as f32 / (steps - 1).max(1) as f32 * (stops.len() - 1) as f32;
+            let below = (position as usize).min(stops.len() - 1);
+            let above = (below + 1).min(stops.len() - 1);
+            let fraction = position - below as f32;
+            let channel = |c: usize| (stops[below][c] as f32 + (stops[above][c] as f32 - stops[below][c] as f32) * fraction).round() as u8;
+            Color32::from_rgb(channel(0), channel(1), channel(2))
+        })
+        .collect()
+}
+
+// A fractional octave band, edges and centre in Hz
+pub(crate) struct OctaveBand {
+    pub(crate) low: f32,
+    pub(crate) centre: f32,
+    pub(crate) high: f32,
+}
+
+impl OctaveBand {
+    // ISO 266 name of the band, its centre rounded to the R40 preferred numbers (31.5, 63, 125...)
+    pub(crate) fn nominal(&self) -> f32 {
+        const R40: [f32; 41] = [
+            1.0, 1.06, 1.12, 1.18, 1.25, 1.32, 1.4, 1.5, 1.6, 1.7, 1.8, 1.9, 2.0, 2.12, 2.24, 2.36, 2.5, 2.65, 2.8, 3.0,
+            3.15, 3.35, 3.55, 3.75, 4.0, 4.25, 4.5, 4.75, 5.0, 5.3, 5.6, 6.0, 6.3, 6.7, 7.1, 7.5, 8.0, 8.5, 9.0, 9.5, 10.0,
+        ];
+        let decade = 10.0f32.powf(self.centre.log10().floor());
+        let mantissa = self.centre / decade;
+        let nearest = R40.iter().fold(R40[0], |best, r| if (r / mantissa).ln().abs() < (best / mantissa).ln().abs() { *r } else { best });
+        nearest * decade
+    }
+}
+
+// The bands_per_octave bands that reach into min_freq..max_freq. Centres are the IEC 61260 base 10
+// midband frequencies around 1 kHz, with an even number of bands per octave straddling 1 kHz instead
+// of sitting on it
+pub(crate) fn octave_bands(bands_per_octave: u32, (min_freq, max_freq): (f32, f32)) -> Vec<OctaveBand> {
+    let bands = bands_per_octave as f32;
+    // Octave ratio of the base 10 system, 10^0.3 rather than exactly 2
+    let octave = 10.0f32.powf(0.3);
+    let offset = if bands_per_octave % 2 == 0 { 0.5 } else { 0.0 };
+    let index = |freq: f32| bands * (freq / 1000.0).log10() / 0.3 - offset;
+    let first = index(min_freq).floor() as i32 - 1;
+    let last = index(max_freq).ceil() as i32 + 1;
+    (first..=last)
+        .map(|x| {
+            let centre = 1000.0 * octave.powf((x as f32 + offset) / bands);
+            let half_band = octave.powf(0.5 / bands);
+            OctaveBand { low: centre / half_band, centre, high: centre * half_band }
+        })
+        .filter(|band| band.high > min_freq && band.low < max_freq)
+        .collect()
+}
+
+// Level of each band from a magnitude spectrum, summing the power of the bins inside it. Each bin
+// stands for half a bin either side of it and is split between the bands it straddles, and the sum
+// is taken back down by the window's noise bandwidth so a sine reads its own level. Bands narrower
+// than a bin read the spectrum at their centre instead
+pub(crate) fn band_levels(magnitudes: &[f32], bin_hz: f32, bands: &[OctaveBand], noise_bandwidth: f32) -> Vec<f32> {
+    let usable = &magnitudes[..magnitudes.len() / 2];
+    if usable.is_empty() {
+        return vec![0.0; bands.len()];
+    }
+    bands
+        .iter()
+        .map(|band| {
+            let (low, high) = (band.low / bin_hz, band.high / bin_hz);
+            if high - low < 1.0 {
+                return magnitude_at(usable, band.centre / bin_hz);
+            }
+            let first = (low + 0.5).floor() as usize;
+            let last = ((high + 0.5).floor() as usize).min(usable.len().saturating_sub(1));
+            let power: f32 = (first..=last)
+                .map(|bin| {
+                    let overlap = ((bin as f32 + 0.5).min(high) - (bin as f32 - 0.5).max(low)).max(0.0);
+                    overlap * usable[bin] * usable[bin]
+                })
+                .sum();
+            (power / noise_bandwidth).sqrt()
+        })
+        .collect()
+}
+
+// Frequency spans where two inputs are both above threshold and within range dB of each other, judged
+// by their levels after weight (the analyzer's tilt) like everything else drawn
+pub(crate) fn masking_regions(
+    a: &[f32],
+    b: &[f32],
+    bin_hz: f32,
+    (min_freq, max_freq): (f32, f32),
+    threshold: f32,
+    range: f32,
+    weight: impl Fn(f32, f32) -> f32,
+) -> Vec<(f32, f32)> {
+    let first = ((min_freq / bin_hz) as usize).max(1);
+    let last = ((max_freq / bin_hz).ceil() as usize).min(a.len().min(b.len()) / 2);
+    let level = |magnitudes: &[f32], bin: usize| util::gain_to_db(weight(bin as f32 * bin_hz, magnitudes[bin]));
+    let mut regions: Vec<(f32, f32)> = Vec::new();
+    let mut run_start: Option<usize> = None;
+    for bin in first..=last {
+        let colliding = bin < last && collision_score(level(a, bin), level(b, bin), threshold, range) > 0.0;
+        match (colliding, run_start) {
+            (true, None) => run_start = Some(bin),
+            (false, Some(start)) => {
+                regions.push(((start as f32 - 0.5) * bin_hz, (bin as f32 - 0.5) * bin_hz));
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+    regions
+}
+
+// How badly two levels in dB collide, 1 when they're equal down to 0 once they're range apart or
+// either is below threshold
+pub(crate) fn collision_score(a: f32, b: f32, threshold: f32, range: f32) -> f32 {
+    if a.min(b) <= threshold {
+        return 0.0;
+    }
+    (1.0 - (a - b).abs() / range).max(0.0)
+}
+
+// Spectrum level at a fractional bin, straight line between the bins either side
+pub(crate) fn magnitude_at(magnitudes: &[f32], bin: f32) -> f32 {
+    if magnitudes.is_empty() || bin < 0.0 {
+        return 0.0;
+    }
+    let below = (bin as usize).min(magnitudes.len() - 1);
+    let above = (below + 1).min(magnitudes.len() - 1);
+    let fraction = bin - below as f32;
+    magnitudes[below] + (magnitudes[above] - magnitudes[below]) * fraction.min(1.0)
+}
+
+// Nearest equal tempered note to a frequency (A4 = 440 Hz) and how many cents off it is
+pub(crate) fn note_name(freq: f32) -> (String, f32) {
+    const NOTES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+    if freq <= 0.0 {
+        return (String::from("-"), 0.0);
+    }
+    let midi = 69.0 + 12.0 * (freq / 440.0).log2();
+    let nearest = midi.round();
+    let octave = (nearest / 12.0).floor() as i32 - 1;
+    let name = NOTES[(nearest as i32).rem_euclid(12) as usize];
+    (format!("{}{}", name, octave), (midi - nearest) * 100.0)
+}
+
+pub(crate) fn frequency_label(freq: f32) -> String {
+    if freq >= 1000.0 {
+        format!("{:.2} kHz", freq / 1000.0)
+    } else {
+        format!("{:.1} Hz", freq)
+    }
+}
+
+pub(crate) struct SpectralPeak {
+    pub(crate) freq: f32,
+    // Magnitude at the interpolated top of the peak
+    pub(crate) magnitude: f32,
+}
+
+// The count loudest local maxima between min_freq and max_freq, loudness judged after weight (the
+// analyzer's tilt) so they match what's drawn. Each peak is placed between bins with a parabola
+// through the dB levels of its bin and the two either side
+pub(crate) fn find_peaks(
+    magnitudes: &[f32],
+    bin_hz: f32,
+    (min_freq, max_freq): (f32, f32),
+    count: usize,
+    weight: impl Fn(f32, f32) -> f32,
+) -> Vec<SpectralPeak> {
+    let first = ((min_freq / bin_hz) as usize).max(1);
+    let last = ((max_freq / bin_hz).ceil() as usize).min(magnitudes.len().saturating_sub(1));
+    let db = |bin: usize| util::gain_to_db(magnitudes[bin]);
+
+    let mut peaks: Vec<SpectralPeak> = (first..last)
+        .filter(|bin| magnitudes[*bin] > magnitudes[bin - 1] && magnitudes[*bin] >= magnitudes[bin + 1])
+        .map(|bin| {
+            let (left, center, right) = (db(bin - 1), db(bin), db(bin + 1));
+            let curvature = left - 2.0 * center + right;
+            let offset = if curvature < 0.0 { (0.5 * (left - right) / curvature).clamp(-0.5, 0.5) } else { 0.0 };
+            SpectralPeak {
+                freq: (bin as f32 + offset) * bin_hz,
+                magnitude: util::db_to_gain(center - 0.25 * (left - right) * offset),
+            }
+        })
+        .collect();
+    peaks.sort_by(|a, b| weight(b.freq, b.magnitude).total_cmp(&weight(a.freq, a.magnitude)));
+    peaks.truncate(count);
+    peaks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FftWindow;
+
+    // Windowed spectrum scaled like complex_spectrum()
+    fn frame(samples: &[f32], window: FftWindow) -> Vec<Complex<f32>> {
+        let len = samples.len();
+        let mut samples: Vec<Complex<f32>> =
+            samples.iter().zip(window.coefficients(len)).map(|(sample, weight)| Complex::new(sample * weight, 0.0)).collect();
+        FftPlanner::<f32>::new().plan_fft_forward(len).process(&mut samples);
+        samples.iter().map(|c| c * (2.0 / len as f32)).collect()
+    }
+
+    fn spectrum(samples: &[f32], window: FftWindow) -> Vec<f32> {
+        frame(samples, window).iter().map(|c| c.norm()).collect()
+    }
+
+    // Uniform noise from -1 to 1, the same every run
+    fn noise(seed: &mut u32) -> f32 {
+        *seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+        (*seed >> 8) as f32 / (1 << 24) as f32 * 2.0 - 1.0
+    }
+
+    // A unit sine sitting at a fractional bin
+    fn sine_spectrum(bin: f32, len: usize, window: FftWindow) -> Vec<f32> {
+        let samples: Vec<f32> = (0..len).map(|n| (std::f32::consts::TAU * bin * n as f32 / len as f32).sin()).collect();
+        spectrum(&samples, window)
+    }
+
+    #[test]
+    fn peaks_are_placed_between_bins() {
+        for (window, tolerance) in [(FftWindow::Hann, 0.05), (FftWindow::BlackmanHarris, 0.01)] {
+            for step in 0..=10 {
+                let bin = 100.0 + step as f32 * 0.1;
+                let magnitudes = sine_spectrum(bin, 1024, window);
+                let peaks = find_peaks(&magnitudes[..512], 1.0, (1.0, 512.0), 1, |_, magnitude| magnitude);
+                assert_eq!(peaks.len(), 1);
+                assert!((peaks[0].freq - bin).abs() < tolerance, "sine at bin {} found at {}", bin, peaks[0].freq);
+                assert!(util::gain_to_db(peaks[0].magnitude).abs() < 0.5, "sine at bin {} read {}", bin, peaks[0].magnitude);
+            }
+        }
+    }
+
+    #[test]
+    fn peaks_are_loudest_first() {
+        let magnitudes: Vec<f32> = sine_spectrum(50.0, 1024, FftWindow::Hann)
+            .iter()
+            .zip(sine_spectrum(200.0, 1024, FftWindow::Hann))
+            .map(|(quiet, loud)| 0.1 * quiet + loud)
+            .collect();
+        let peaks = find_peaks(&magnitudes[..512], 1.0, (1.0, 512.0), 2, |_, magnitude| magnitude);
+        assert!((peaks[0].freq - 200.0).abs() < 0.01);
+        assert!((peaks[1].freq - 50.0).abs() < 0.01);
+
+        // Weighted by the tilt, not by the raw level
+        let peaks = find_peaks(&magnitudes[..512], 1.0, (1.0, 512.0), 1, |freq, magnitude| magnitude / (freq * freq));
+        assert!((peaks[0].freq - 50.0).abs() < 0.01);
+
+        let peaks = find_peaks(&magnitudes[..512], 1.0, (100.0, 512.0), 1, |freq, magnitude| magnitude / (freq * freq));
+        assert!((peaks[0].freq - 200.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn peaks_need_a_bin_either_side() {
+        // Maxima on DC and on the last bin below Nyquist have no neighbour to interpolate with
+        let magnitudes = [1.0, 0.5, 0.25, 0.5, 1.0];
+        assert!(find_peaks(&magnitudes, 1.0, (0.0, 100.0), 4, |_, magnitude| magnitude).is_empty());
+
+        // One bin in from the edges they're found, a range past Nyquist is cut to the spectrum
+        let magnitudes = [0.5, 1.0, 0.25, 1.0, 0.5];
+        let peaks = find_peaks(&magnitudes, 1.0, (0.0, 100.0), 4, |_, magnitude| magnitude);
+        assert_eq!(peaks.len(), 2);
+        assert!(peaks.iter().all(|peak| (peak.freq - 1.0).abs() < 0.5 || (peak.freq - 3.0).abs() < 0.5));
+    }
+
+    #[test]
+    fn notes_round_to_the_nearest_semitone() {
+        let cents = |semitones: f32| 440.0 * 2.0f32.powf(semitones / 12.0);
+
+        let (name, offset) = note_name(440.0);
+        assert_eq!(name, "A4");
+        assert!(offset.abs() < 0.01);
+
+        let (name, offset) = note_name(cents(0.49));
+        assert_eq!(name, "A4");
+        assert!((offset - 49.0).abs() < 0.01);
+
+        let (name, offset) = note_name(cents(0.51));
+        assert_eq!(name, "A#4");
+        assert!((offset + 49.0).abs() < 0.01);
+
+        // The octave number turns over at C
+        assert_eq!(note_name(cents(-10.0)).0, "B3");
+        assert_eq!(note_name(cents(-9.0)).0, "C4");
+        assert_eq!(note_name(27.5).0, "A0");
+        assert_eq!(note_name(0.0).0, "-");
+    }
+
+    #[test]
+    fn third_octave_centres_are_the_nominal_series() {
+        const NOMINAL: [f32; 30] = [
+            25.0, 31.5, 40.0, 50.0, 63.0, 80.0, 100.0, 125.0, 160.0, 200.0, 250.0, 315.0, 400.0, 500.0, 630.0, 800.0,
+            1000.0, 1250.0, 1600.0, 2000.0, 2500.0, 3150.0, 4000.0, 5000.0, 6300.0, 8000.0, 10000.0, 12500.0, 16000.0,
+            20000.0,
+        ];
+        let bands = octave_bands(3, (25.0, 20000.0));
+        assert_eq!(bands.len(), NOMINAL.len());
+        for (band, nominal) in bands.iter().zip(NOMINAL) {
+            assert!((band.nominal() / nominal - 1.0).abs() < 1e-4, "{} Hz band named {}", nominal, band.nominal());
+            // Exact centres stay within the rounding of the nominal values
+            assert!((band.centre / nominal - 1.0).abs() < 0.02, "{} Hz band centred on {}", nominal, band.centre);
+        }
+        for pair in bands.windows(2) {
+            assert!((pair[0].high / pair[1].low - 1.0).abs() < 1e-4);
+        }
+
+        let octaves: Vec<f32> = octave_bands(1, (31.5, 16000.0)).iter().map(OctaveBand::nominal).collect();
+        let expected = [31.5, 63.0, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0, 16000.0];
+        assert_eq!(octaves.len(), expected.len());
+        assert!(octaves.iter().zip(expected).all(|(octave, nominal)| (octave / nominal - 1.0).abs() < 1e-4));
+    }
+
+    #[test]
+    fn flat_spectrum_sums_to_the_bandwidth() {
+        let bin_hz = 10.0;
+        let magnitudes = vec![1.0; 8192];
+        let bands = octave_bands(3, (100.0, 20000.0));
+        for (band, level) in bands.iter().zip(band_levels(&magnitudes, bin_hz, &bands, 1.5)) {
+            let expected = (band.high - band.low) / bin_hz / 1.5;
+            assert!((level * level / expected - 1.0).abs() < 1e-3, "{} Hz band read {}", band.centre, level);
+        }
+    }
+
+    #[test]
+    fn white_noise_band_power_matches_the_bandwidth() {
+        let (len, sample_rate, frames) = (4096, 48000.0, 128);
+        let bin_hz = sample_rate / len as f32;
+        let bands = octave_bands(3, (1000.0, 20000.0));
+        let mut seed = 1;
+
+        for window in [FftWindow::Hann, FftWindow::BlackmanHarris, FftWindow::FlatTop] {
+            let mut power = vec![0.0; bands.len()];
+            for _ in 0..frames {
+                let samples: Vec<f32> = (0..len).map(|_| noise(&mut seed)).collect();
+                let levels = band_levels(&spectrum(&samples, window), bin_hz, &bands, window.noise_bandwidth());
+                for (power, level) in power.iter_mut().zip(levels) {
+                    *power += level * level / frames as f32;
+                }
+            }
+            // Uniform noise has a variance of 1/3, spread evenly up to Nyquist. Levels read like a sine's
+            // peak, twice the variance
+            for (band, power) in bands.iter().zip(power) {
+                let expected = 2.0 / 3.0 * (band.high - band.low) / (sample_rate / 2.0);
+                assert!((power / expected - 1.0).abs() < 0.1, "{} Hz band read {} of {}", band.centre, power, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn known_delay_measures_flat_with_its_group_delay() {
+        let (len, hop, frames, delay, sample_rate) = (1024, 512, 64, 12, 48000.0);
+        let bin_hz = sample_rate / len as f32;
+        let mut seed = 1;
+        let reference: Vec<f32> = (0..delay + hop * frames + len).map(|_| noise(&mut seed)).collect();
+        let settings = Ballistics {
+            averaging: SpectrumAveraging::Exponential,
+            average_time: 0.1,
+            peak_hold: false,
+            hold_time: 0.0,
+            release: 0.0,
+            frame_time: hop as f32 / sample_rate,
+        };
+
+        // Main is the reference delay samples late
+        let mut transfer = TransferFunction::default();
+        for start in (0..frames).map(|index| delay + index * hop) {
+            let measured = frame(&reference[start - delay..start - delay + len], FftWindow::Hann);
+            transfer.add_frame(1, &frame(&reference[start..start + len], FftWindow::Hann), &measured, &settings);
+        }
+        let response = transfer.response();
+        assert_eq!(response.len(), len / 2);
+
+        let bins = 2..len / 2 - 2;
+        for point in response[bins.clone()].iter().map(|point| point.unwrap()) {
+            assert!((point.gain - 1.0).abs() < 0.05, "gain {}", point.gain);
+            assert!(point.coherence > 0.98, "coherence {}", point.coherence);
+        }
+        // The delay turns the phase by a whole turn every len / delay bins
+        let unwrapped = unwrap_phase(&response.iter().map(|point| point.map(|point| point.phase)).collect::<Vec<_>>());
+        for (bin, phase) in unwrapped.iter().enumerate().skip(1) {
+            let expected = -std::f32::consts::TAU * bin as f32 * delay as f32 / len as f32;
+            assert!((phase.unwrap() - expected).abs() < 0.1, "bin {} at {} rad", bin, phase.unwrap());
+        }
+        let delays: Vec<f32> = bins.map(|bin| group_delay(&response, bin, bin_hz).unwrap()).collect();
+        let mean = delays.iter().sum::<f32>() / delays.len() as f32;
+        assert!((mean * sample_rate - delay as f32).abs() < 0.05, "group delay {} samples", mean * sample_rate);
+    }
+
+    #[test]
+    fn group_delay_needs_both_neighbours() {
+        let point = |phase| Some(TransferPoint { gain: 1.0, phase, coherence: 1.0 });
+        let response = [point(0.0), point(-0.1), point(-0.2), None, point(-0.4)];
+        assert!(group_delay(&response, 0, 1.0).is_none());
+        assert!(group_delay(&response, 2, 1.0).is_none());
+        assert!(group_delay(&response, 4, 1.0).is_none());
+        let delay = group_delay(&response, 1, 1.0).unwrap();
+        assert!((delay - 0.1 / std::f32::consts::TAU).abs() < 1e-6);
+
+        // A step across the wrap reads as the short way round
+        let response = [point(3.0), point(3.1), point(-3.0)];
+        let delay = group_delay(&response, 1, 1.0).unwrap();
+        assert!((delay + (std::f32::consts::TAU - 6.0) / (2.0 * std::f32::consts::TAU)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn unwrapped_phase_steps_over_gaps() {
+        let unwrapped = unwrap_phase(&[None, Some(3.0), Some(-3.0), None, Some(3.0), Some(-3.0)]);
+        let expected = [None, Some(3.0), Some(std::f32::consts::TAU - 3.0), None, Some(3.0), Some(std::f32::consts::TAU - 3.0)];
+        assert_eq!(unwrapped.len(), expected.len());
+        for (phase, expected) in unwrapped.iter().zip(expected) {
+            match (phase, expected) {
+                (Some(phase), Some(expected)) => assert!((phase - expected).abs() < 1e-5, "{} for {}", phase, expected),
+                (phase, expected) => assert_eq!(phase.is_some(), expected.is_some()),
+            }
+        }
+
+        // A steady fall keeps falling however many turns it makes
+        let wrapped: Vec<Option<f32>> =
+            (0..100).map(|bin| Some((-0.5 * bin as f32 + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI)).collect();
+        for (bin, phase) in unwrap_phase(&wrapped).iter().enumerate() {
+            assert!((phase.unwrap() + 0.5 * bin as f32).abs() < 1e-3);
+        }
+    }
+}
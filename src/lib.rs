@@ -6,11 +6,12 @@ use rustfft::{num_complex::Complex, FftPlanner};
 use std::{
     env,
     sync::{
-        atomic::{AtomicBool, AtomicI32, AtomicU8, AtomicUsize, Ordering},
-        Arc, Mutex, RwLock,
+        atomic::{AtomicBool, AtomicI32, AtomicU32, AtomicUsize, Ordering},
+        Arc, Mutex, RwLock, RwLockReadGuard,
     },
 };
 
+mod analyzer;
 mod slim_checkbox;
 mod scrollscope_gui;
 
@@ -25,74 +26,165 @@ fn calculate_buffer_size(sample_rate: f32, max_display_ms: f32) -> usize {
 }
 
 const NUM_CHANNELS: usize = 8; // Main + 5 aux + beat lines + sum
+const SIGNAL_CHANNELS: [usize; 7] = [0, 1, 2, 3, 4, 5, 7]; // Everything except the beat lines
 const MAX_DISPLAY_MS: f32 = 1000.0; // Maximum display time in milliseconds
+const DEFAULT_TIME_SIGNATURE: (i32, i32) = (4, 4); // Used when the host doesn't report one
+const HISTORY_MS: f32 = 4000.0; // Extra capture kept behind the visible window for Hold scrubbing
+const MAX_LONG_DISPLAY_MS: f32 = 300000.0; // Longest window, drawn from the envelope mipmap past MAX_DISPLAY_MS
+const ENVELOPE_BASE: usize = 64; // Samples per bucket on the finest mipmap level
+const ENVELOPE_LEVELS: usize = 9; // Each level doubles the bucket size of the one below
+const ENVELOPE_LEVEL_LEN: usize = 4096; // Buckets kept per level
+const ANALYZER_FREQ_LIMITS: (f32, f32) = (10.0, 24000.0); // Widest the analyzer zooms out to, in Hz
+const ANALYZER_DB_LIMITS: (f32, f32) = (-100.0, 24.0); // Same in dBFS, -100 is where gain_to_db() bottoms out
+
+// Running min/max/RMS of the samples folded into one display point
+#[derive(Clone, Copy)]
+struct EnvelopeBucket {
+    min: f32,
+    max: f32,
+    sum: f32,
+    sum_squares: f32,
+    count: i32,
+}
+
+impl EnvelopeBucket {
+    const EMPTY: Self = Self {
+        min: f32::MAX,
+        max: f32::MIN,
+        sum: 0.0,
+        sum_squares: 0.0,
+        count: 0,
+    };
+
+    fn from_sample(sample: f32) -> Self {
+        let mut bucket = Self::EMPTY;
+        bucket.add(sample);
+        bucket
+    }
+
+    fn add(&mut self, sample: f32) {
+        self.min = self.min.min(sample);
+        self.max = self.max.max(sample);
+        self.sum += sample;
+        self.sum_squares += sample * sample;
+        self.count += 1;
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.sum += other.sum;
+        self.sum_squares += other.sum_squares;
+        self.count += other.count;
+    }
 
-// Channel data container
+    fn mean(&self) -> f32 {
+        if self.count > 0 { self.sum / self.count as f32 } else { 0.0 }
+    }
+
+    fn rms(&self) -> f32 {
+        if self.count > 0 { (self.sum_squares / self.count as f32).sqrt() } else { 0.0 }
+    }
+}
+
+// Channel data container - a single producer ring buffer. Samples are stored as f32 bits in
+// atomics so the audio thread can publish them without ever waiting on the editor
 struct ChannelBuffer {
-    data: Vec<f32>,
-    write_index: usize,
+    // The sample, or the bucket maximum when decimating by envelope
+    data: Box<[AtomicU32]>,
+    // Bucket minimum and RMS, equal to the sample (and its magnitude) when skipping
+    low: Box<[AtomicU32]>,
+    rms: Box<[AtomicU32]>,
+    write_index: AtomicUsize,
+}
+
+fn atomic_ring(size: usize) -> Box<[AtomicU32]> {
+    (0..size).map(|_| AtomicU32::new(0)).collect()
 }
 
 impl ChannelBuffer {
     fn new(size: usize) -> Self {
         Self {
-            data: vec![0.0; size],
-            write_index: 0,
+            data: atomic_ring(size),
+            low: atomic_ring(size),
+            rms: atomic_ring(size),
+            write_index: AtomicUsize::new(0),
         }
     }
 
-    fn resize(&mut self, new_size: usize) {
-        if new_size > self.data.len() {
-            self.data.resize(new_size, 0.0);
-        }
-        // Don't shrink - avoid reallocation
+    fn push_sample(&self, sample: f32) {
+        self.push_point(sample, sample, sample.abs());
+    }
+
+    // The ring always wraps at full capacity so everything behind the visible window is history
+    fn push_point(&self, high: f32, low: f32, rms: f32) {
+        let index = self.write_index.load(Ordering::Relaxed) % self.data.len();
+        self.update_point(index, high, low, rms);
+        // Release so a reader that sees the new index also sees the sample behind it
+        self.write_index.store((index + 1) % self.data.len(), Ordering::Release);
     }
 
-    fn push_sample(&mut self, sample: f32, buffer_len: usize) {
-        self.data[self.write_index % buffer_len] = sample;
-        self.write_index = (self.write_index + 1) % buffer_len;
+    fn update_sample(&self, index: usize, sample: f32) {
+        self.update_point(index, sample, sample, sample.abs());
     }
 
-    fn update_sample(&mut self, index: usize, sample: f32) {
+    fn update_point(&self, index: usize, high: f32, low: f32, rms: f32) {
         if index < self.data.len() {
-            self.data[index] = sample;
+            self.data[index].store(high.to_bits(), Ordering::Relaxed);
+            self.low[index].store(low.to_bits(), Ordering::Relaxed);
+            self.rms[index].store(rms.to_bits(), Ordering::Relaxed);
         }
     }
 
-    fn zero_out(&mut self) {
-        for x in 0..self.data.len() {
-            self.data[x] = 0.0;
+    fn clear(&self, range: std::ops::Range<usize>) {
+        for ring in [&self.data, &self.low, &self.rms] {
+            for slot in ring[range.clone()].iter() {
+                slot.store(0, Ordering::Relaxed);
+            }
         }
-        self.write_index = 0;
     }
 
-    fn get_sample(&self, index: usize) -> Option<f32> {
-        if index < self.data.len() {
-            Some(self.data[index])
-        } else {
-            None
+    fn copy_point(&self, from: usize, to: usize) {
+        for ring in [&self.data, &self.low, &self.rms] {
+            ring[to].store(ring[from].load(Ordering::Relaxed), Ordering::Relaxed);
         }
     }
 
-    fn get_samples(&self, buffer_len: usize) -> Vec<f32> {
+    fn sample(&self, index: usize) -> f32 {
+        f32::from_bits(self.data[index].load(Ordering::Relaxed))
+    }
+
+    // Start of the buffer_len window that ends scrub_offset points before the write head
+    fn window_start(&self, buffer_len: usize, scrub_offset: usize) -> usize {
+        self.window_start_from(self.write_index.load(Ordering::Acquire), buffer_len, scrub_offset)
+    }
+
+    // The same for a window ending scrub_offset points before end instead of the live write head
+    fn window_start_from(&self, end: usize, buffer_len: usize, scrub_offset: usize) -> usize {
+        let capacity = self.data.len();
+        let scrub_offset = scrub_offset.min(capacity - buffer_len);
+        (end % capacity + 2 * capacity - buffer_len - scrub_offset) % capacity
+    }
+
+    fn read_ring(&self, ring: &[AtomicU32], buffer_len: usize, scrub_offset: usize) -> Vec<f32> {
         let mut samples = Vec::with_capacity(buffer_len);
-        let start_idx = self.write_index;
+        let start_idx = self.window_start(buffer_len, scrub_offset);
         
         for i in 0..buffer_len {
-            let idx = (start_idx + i) % buffer_len;
-            samples.push(self.data[idx]);
+            let idx = (start_idx + i) % ring.len();
+            samples.push(f32::from_bits(ring[idx].load(Ordering::Relaxed)));
         }
         
         samples
     }
 
-    fn get_complex_samples(&self, length: usize, buffer_len: usize) -> Vec<Complex<f32>> {
-        let mut complex_samples = Vec::with_capacity(length.min(buffer_len));
-        let start_idx = self.write_index;
+    fn get_complex_samples(&self, length: usize, end: usize, scrub_offset: usize) -> Vec<Complex<f32>> {
+        let mut complex_samples = Vec::with_capacity(length);
+        let start_idx = self.window_start_from(end, length, scrub_offset);
         
-        for i in 0..length.min(buffer_len) {
-            let idx = (start_idx + i) % buffer_len;
-            let sample = self.data[idx];
+        for i in 0..length {
+            let idx = (start_idx + i) % self.data.len();
+            let sample = self.sample(idx);
             complex_samples.push(Complex::new(flush_denormal_bits(sample), 0.0));
         }
         
@@ -103,10 +195,75 @@ impl ChannelBuffer {
 // More efficient buffer implementation
 struct OptimizedBuffer {
     internal_length: AtomicUsize,
-    // Use RwLock for better read concurrency
+    capacity: AtomicUsize,
+    // How far behind the write head the editor is looking while held
+    scrub_offset: AtomicUsize,
+    // The lock only guards reallocation in initialize(). The audio thread uses try_read() and the
+    // editor only ever takes read locks, so neither side can block the other
     buffers: RwLock<Vec<ChannelBuffer>>,
 }
 
+// Audio thread handle to an OptimizedBuffer, taken once per process() call
+struct BufferWriter<'a> {
+    buffers: RwLockReadGuard<'a, Vec<ChannelBuffer>>,
+    buffer_len: usize,
+}
+
+impl BufferWriter<'_> {
+    fn capacity(&self) -> usize {
+        self.buffers[0].data.len()
+    }
+
+    // Points in the visible window
+    fn window_len(&self) -> usize {
+        self.buffer_len
+    }
+
+    fn push_sample(&self, channel: usize, sample: f32) {
+        self.buffers[channel].push_sample(sample);
+    }
+
+    fn push_point(&self, channel: usize, point: &EnvelopeBucket) {
+        self.buffers[channel].push_point(point.max, point.min, point.rms());
+    }
+
+    // In-place writers fill from index 0, so park the write heads where the visible window starts there
+    fn pin_window(&self) {
+        for channel in self.buffers.iter() {
+            channel.write_index.store(self.buffer_len, Ordering::Release);
+        }
+    }
+
+    fn update_sample(&self, channel: usize, index: usize, sample: f32) {
+        self.buffers[channel].update_sample(index, sample);
+    }
+
+    fn update_point(&self, channel: usize, index: usize, point: &EnvelopeBucket) {
+        self.buffers[channel].update_point(index, point.max, point.min, point.rms());
+    }
+
+    // Blanks the rest of the window from start, where an older and longer sync cycle left points behind
+    fn clear_window_from(&self, start: usize) {
+        let start = start.min(self.buffer_len);
+        for channel in self.buffers.iter() {
+            channel.clear(start..self.buffer_len);
+        }
+    }
+
+    // The trigger keeps its pre-trigger points in a ring just past the window. When it fires they
+    // are copied oldest first to the front of the window and the capture carries on after them
+    fn unroll_pretrigger(&self, pre_points: usize, oldest: usize) {
+        if pre_points == 0 || self.buffer_len + pre_points > self.capacity() {
+            return;
+        }
+        for channel in self.buffers.iter() {
+            for i in 0..pre_points {
+                channel.copy_point(self.buffer_len + (oldest + i) % pre_points, i);
+            }
+        }
+    }
+}
+
 impl OptimizedBuffer {
     fn new(size: usize) -> Self {
         let mut channels = Vec::with_capacity(NUM_CHANNELS);
@@ -116,81 +273,655 @@ impl OptimizedBuffer {
         
         Self {
             internal_length: AtomicUsize::new(size),
+            capacity: AtomicUsize::new(size),
+            scrub_offset: AtomicUsize::new(0),
             buffers: RwLock::new(channels),
         }
     }
-    
-    fn update_internal_length(&self, new_length: usize) {
-        self.internal_length.store(new_length, Ordering::Release);
+
+    // Grows the backing storage - this allocates so never call it from process()
+    fn reserve(&self, capacity: usize) {
         let mut buffers = self.buffers.write().unwrap();
-        for channel in buffers.iter_mut() {
-            channel.resize(new_length);
+        if buffers[0].data.len() < capacity {
+            *buffers = (0..NUM_CHANNELS).map(|_| ChannelBuffer::new(capacity)).collect();
+            self.capacity.store(capacity, Ordering::Release);
         }
     }
+    
+    fn update_internal_length(&self, new_length: usize) {
+        let capacity = self.capacity.load(Ordering::Acquire);
+        self.internal_length.store(new_length.clamp(1, capacity), Ordering::Release);
+    }
 
-    // Batch process multiple samples with a single lock acquisition
-    fn push_samples(&self, channel_data: &[(usize, f32)]) {
-        let buffer_len = self.internal_length.load(Ordering::Acquire);
-        let mut buffers = self.buffers.write().unwrap();
-        
-        for &(channel, sample) in channel_data {
-            if channel < NUM_CHANNELS {
-                buffers[channel].push_sample(sample, buffer_len);
-            }
-        }
+    fn set_scrub_offset(&self, offset: usize) {
+        self.scrub_offset.store(offset.min(self.history_len()), Ordering::Relaxed);
     }
 
-    fn update_sample(&self, channel: usize, index: usize, sample: f32) {
+    fn scrub_offset(&self) -> usize {
+        self.scrub_offset.load(Ordering::Relaxed)
+    }
+
+    // Points stored behind the visible window
+    fn history_len(&self) -> usize {
+        self.capacity.load(Ordering::Acquire).saturating_sub(self.internal_length.load(Ordering::Acquire))
+    }
+
+    // Returns None only while initialize() is reallocating, the block is just not drawn then
+    fn writer(&self) -> Option<BufferWriter<'_>> {
+        let buffers = self.buffers.try_read().ok()?;
+        let buffer_len = self.internal_length.load(Ordering::Acquire).min(buffers[0].data.len());
+        Some(BufferWriter { buffers, buffer_len })
+    }
+
+    fn get_samples(&self, channel: usize) -> Vec<f32> {
         if channel >= NUM_CHANNELS {
-            return;
+            return Vec::new();
         }
 
-        let buffers = self.buffers.write().unwrap();
-        if let Some(buffer) = buffers.get(channel) {
-            if index < buffer.data.len() {
-                // Use const cast to avoid double locking
-                unsafe {
-                    let buffer_ptr = buffer as *const ChannelBuffer as *mut ChannelBuffer;
-                    (*buffer_ptr).update_sample(index, sample);
-                }
-            }
-        }
+        let buffers = self.buffers.read().unwrap();
+        let buffer_len = self.internal_length.load(Ordering::Acquire).min(buffers[channel].data.len());
+        buffers[channel].read_ring(&buffers[channel].data, buffer_len, self.scrub_offset())
     }
 
-    fn get_sample(&self, channel: usize, index: usize) -> Option<f32> {
+    // Bucket minimums, lined up with get_samples()
+    fn get_low_samples(&self, channel: usize) -> Vec<f32> {
         if channel >= NUM_CHANNELS {
-            return None;
+            return Vec::new();
         }
 
         let buffers = self.buffers.read().unwrap();
-        buffers[channel].get_sample(index)
+        let buffer_len = self.internal_length.load(Ordering::Acquire).min(buffers[channel].data.len());
+        buffers[channel].read_ring(&buffers[channel].low, buffer_len, self.scrub_offset())
     }
 
-    fn get_samples(&self, channel: usize) -> Vec<f32> {
+    // Bucket RMS values, lined up with get_samples()
+    fn get_rms_samples(&self, channel: usize) -> Vec<f32> {
         if channel >= NUM_CHANNELS {
             return Vec::new();
         }
 
-        let buffer_len = self.internal_length.load(Ordering::Acquire);
         let buffers = self.buffers.read().unwrap();
-        buffers[channel].get_samples(buffer_len)
+        let buffer_len = self.internal_length.load(Ordering::Acquire).min(buffers[channel].data.len());
+        buffers[channel].read_ring(&buffers[channel].rms, buffer_len, self.scrub_offset())
     }
 
-    fn get_complex_samples_with_length(&self, channel: usize, length: usize) -> Vec<Complex<f32>> {
+    // The analyzer reads its own length from anywhere in the ring. The read ends at the ring position
+    // end (or the scrub offset before it while held), taken from position_before() so the left and
+    // right rings can be read over the same points
+    fn get_complex_samples_with_length(&self, channel: usize, length: usize, end: usize) -> Vec<Complex<f32>> {
         if channel >= NUM_CHANNELS {
             return Vec::new();
         }
 
-        let buffer_len = self.internal_length.load(Ordering::Acquire);
         let buffers = self.buffers.read().unwrap();
-        buffers[channel].get_complex_samples(length, buffer_len)
+        let length = length.min(buffers[channel].data.len());
+        buffers[channel].get_complex_samples(length, end, self.scrub_offset())
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity.load(Ordering::Acquire)
     }
+
+    // Where the next point goes, the analyzer uses it to count what arrived since it last looked
+    fn write_position(&self) -> usize {
+        self.buffers.read().unwrap()[0].write_index.load(Ordering::Acquire)
+    }
+
+    // Ring position behind points before a write position
+    fn position_before(&self, write: usize, behind: usize) -> usize {
+        let capacity = self.capacity().max(1);
+        (write % capacity + capacity - behind % capacity) % capacity
+    }
+}
+
+// One resolution of the long view envelope. Buckets for every channel sit side by side so all
+// channels share a single write head
+struct EnvelopeLevel {
+    min: Box<[AtomicU32]>,
+    max: Box<[AtomicU32]>,
+    write_index: AtomicUsize,
 }
 
-#[derive(Enum, Clone, PartialEq)]
+impl EnvelopeLevel {
+    fn new() -> Self {
+        Self {
+            min: atomic_ring(ENVELOPE_LEVEL_LEN * NUM_CHANNELS),
+            max: atomic_ring(ENVELOPE_LEVEL_LEN * NUM_CHANNELS),
+            write_index: AtomicUsize::new(0),
+        }
+    }
+
+    fn push(&self, buckets: &[EnvelopeBucket; NUM_CHANNELS]) {
+        let index = self.write_index.load(Ordering::Relaxed);
+        for (channel, bucket) in buckets.iter().enumerate() {
+            // Channels that never got a value (the beat lines between beats) read as silence
+            let (min, max) = if bucket.min <= bucket.max { (bucket.min, bucket.max) } else { (0.0, 0.0) };
+            self.min[index * NUM_CHANNELS + channel].store(min.to_bits(), Ordering::Relaxed);
+            self.max[index * NUM_CHANNELS + channel].store(max.to_bits(), Ordering::Relaxed);
+        }
+        self.write_index.store((index + 1) % ENVELOPE_LEVEL_LEN, Ordering::Release);
+    }
+}
+
+// Min/max mipmap used when the display window is longer than the sample ring can hold.
+// Level 0 buckets hold ENVELOPE_BASE samples and every level above halves the resolution,
+// so a window of minutes still only needs a couple of thousand points to draw
+struct EnvelopeStore {
+    levels: Vec<EnvelopeLevel>,
+}
+
+impl EnvelopeStore {
+    fn new() -> Self {
+        Self {
+            levels: (0..ENVELOPE_LEVELS).map(|_| EnvelopeLevel::new()).collect(),
+        }
+    }
+
+    // Finest level that covers window_samples in at most max_points buckets
+    fn level_for(window_samples: usize, max_points: usize) -> usize {
+        (0..ENVELOPE_LEVELS)
+            .find(|level| window_samples / (ENVELOPE_BASE << level) <= max_points)
+            .unwrap_or(ENVELOPE_LEVELS - 1)
+    }
+
+    // Returns the (min, max) pairs for the last window_samples of a channel, oldest first
+    fn get_envelope(&self, channel: usize, window_samples: usize, max_points: usize) -> (Vec<f32>, Vec<f32>) {
+        if channel >= NUM_CHANNELS {
+            return (Vec::new(), Vec::new());
+        }
+
+        let level = Self::level_for(window_samples, max_points);
+        let store = &self.levels[level];
+        let length = (window_samples / (ENVELOPE_BASE << level)).clamp(1, ENVELOPE_LEVEL_LEN - 1);
+        let start_idx = (store.write_index.load(Ordering::Acquire) + ENVELOPE_LEVEL_LEN - length) % ENVELOPE_LEVEL_LEN;
+
+        let mut mins = Vec::with_capacity(length);
+        let mut maxs = Vec::with_capacity(length);
+        for i in 0..length {
+            let idx = ((start_idx + i) % ENVELOPE_LEVEL_LEN) * NUM_CHANNELS + channel;
+            mins.push(f32::from_bits(store.min[idx].load(Ordering::Relaxed)));
+            maxs.push(f32::from_bits(store.max[idx].load(Ordering::Relaxed)));
+        }
+        (mins, maxs)
+    }
+}
+
+// Audio thread side of an EnvelopeStore - the partially filled bucket of every level
+#[derive(Clone, Copy)]
+struct EnvelopeCascade {
+    pending: [[EnvelopeBucket; NUM_CHANNELS]; ENVELOPE_LEVELS],
+}
+
+impl EnvelopeCascade {
+    const EMPTY: Self = Self {
+        pending: [[EnvelopeBucket::EMPTY; NUM_CHANNELS]; ENVELOPE_LEVELS],
+    };
+
+    fn add_frame(&mut self, store: &EnvelopeStore, frame: &[f32; NUM_CHANNELS]) {
+        for (bucket, value) in self.pending[0].iter_mut().zip(frame) {
+            bucket.add(*value);
+        }
+        if self.pending[0][0].count as usize >= ENVELOPE_BASE {
+            self.complete(store, 0);
+        }
+    }
+
+    // Beat markers span the full height so they survive any amount of folding
+    fn add_beat(&mut self, height: f32) {
+        self.pending[0][6].add(height);
+        self.pending[0][6].add(-height);
+    }
+
+    // Publishes a full bucket and folds it into the level above
+    fn complete(&mut self, store: &EnvelopeStore, level: usize) {
+        let finished = std::mem::replace(&mut self.pending[level], [EnvelopeBucket::EMPTY; NUM_CHANNELS]);
+        store.levels[level].push(&finished);
+
+        if level + 1 < ENVELOPE_LEVELS {
+            for (next, bucket) in self.pending[level + 1].iter_mut().zip(finished.iter()) {
+                next.merge(bucket);
+            }
+            if self.pending[level + 1][0].count as usize >= ENVELOPE_BASE << (level + 1) {
+                self.complete(store, level + 1);
+            }
+        }
+    }
+}
+
+#[derive(Enum, Clone, Copy, PartialEq)]
 pub enum BeatSync {
     Beat,
     Bar,
+    // Note divisions, counted from the start of the bar
+    #[name = "1/2"]
+    Half,
+    #[name = "1/4"]
+    Quarter,
+    #[name = "1/8"]
+    Eighth,
+    #[name = "1/16"]
+    Sixteenth,
+    #[name = "1/4T"]
+    QuarterTriplet,
+    #[name = "1/8T"]
+    EighthTriplet,
+    #[name = "1/16T"]
+    SixteenthTriplet,
+    // Multi-bar windows, counted from bar 1
+    #[name = "2 Bars"]
+    TwoBars,
+    #[name = "4 Bars"]
+    FourBars,
+    #[name = "8 Bars"]
+    EightBars,
+}
+
+impl BeatSync {
+    // Position inside the current sync window and the window length, both in quarter notes
+    fn window(self, meter: &MeterPosition) -> (f64, f64) {
+        let note = |length: f64| (meter.in_bar % length, length);
+        let bars = |count: f64| (meter.bar.rem_euclid(count) * meter.bar_length + meter.in_bar, count * meter.bar_length);
+        match self {
+            BeatSync::Beat => (meter.in_beat, meter.beat_length),
+            BeatSync::Bar => (meter.in_bar, meter.bar_length),
+            BeatSync::Half => note(2.0),
+            BeatSync::Quarter => note(1.0),
+            BeatSync::Eighth => note(0.5),
+            BeatSync::Sixteenth => note(0.25),
+            BeatSync::QuarterTriplet => note(2.0 / 3.0),
+            BeatSync::EighthTriplet => note(1.0 / 3.0),
+            BeatSync::SixteenthTriplet => note(1.0 / 6.0),
+            BeatSync::TwoBars => bars(2.0),
+            BeatSync::FourBars => bars(4.0),
+            BeatSync::EightBars => bars(8.0),
+        }
+    }
+}
+
+#[derive(Enum, Clone, Copy, PartialEq)]
+pub enum Decimation {
+    // Keep every Nth sample (the original behavior)
+    Skip,
+    // Keep each bucket's min and max so peaks between kept samples still show
+    #[name = "Min/Max"]
+    MinMax,
+    // Min/Max plus an RMS band inside it
+    #[name = "Min/Max+RMS"]
+    MinMaxRms,
+}
+
+#[derive(Enum, Clone, Copy, PartialEq)]
+pub enum TriggerMode {
+    // Free running scroll or beat Sync
+    Off,
+    // Triggers on the level, or free runs when a whole window passes without a crossing
+    Auto,
+    // Only redraws on a crossing
+    Normal,
+    // Takes one capture then waits to be armed again
+    Single,
+}
+
+#[derive(Enum, Clone, Copy, PartialEq)]
+pub enum TriggerSlope {
+    Rising,
+    Falling,
+}
+
+// One of the scope's inputs, in the order of the channel buffers and the analyzer's lines
+#[derive(Enum, Clone, Copy, PartialEq)]
+pub enum InputChannel {
+    Main,
+    #[name = "Aux 1"]
+    Aux1,
+    #[name = "Aux 2"]
+    Aux2,
+    #[name = "Aux 3"]
+    Aux3,
+    #[name = "Aux 4"]
+    Aux4,
+    #[name = "Aux 5"]
+    Aux5,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum TriggerPhase {
+    Armed,
+    Capturing,
+    Holdoff,
+    Stopped,
+}
+
+// Per block trigger settings, in samples
+struct TriggerSettings {
+    mode: TriggerMode,
+    slope: TriggerSlope,
+    level: f32,
+    capture_samples: usize,
+    holdoff_samples: usize,
+    auto_timeout: usize,
+    rearm: bool,
+}
+
+// Audio thread trigger bookkeeping carried between blocks
+#[derive(Clone, Copy)]
+struct TriggerState {
+    mode: TriggerMode,
+    phase: TriggerPhase,
+    // Last source sample, to spot the crossing across block boundaries
+    previous: f32,
+    // Samples spent armed, or left to go while capturing or in holdoff
+    counter: usize,
+    // Oldest slot of the pre-trigger ring
+    pre_index: usize,
+    // Next window position the capture writes to, both sides write the same positions
+    capture_index: usize,
+}
+
+impl TriggerState {
+    const IDLE: Self = Self {
+        mode: TriggerMode::Off,
+        phase: TriggerPhase::Armed,
+        previous: 0.0,
+        counter: 0,
+        pre_index: 0,
+        capture_index: 0,
+    };
+
+    // Runs the trigger over one block of the source. Returns the sample the trigger fired on and the
+    // samples that belong to the capture. Only one trigger is taken per block
+    fn scan(&mut self, source: impl Iterator<Item = f32>, settings: &TriggerSettings) -> (Option<usize>, std::ops::Range<usize>) {
+        if settings.mode != self.mode {
+            *self = Self { mode: settings.mode, previous: self.previous, ..Self::IDLE };
+        }
+        if self.phase == TriggerPhase::Stopped && settings.rearm {
+            self.phase = TriggerPhase::Armed;
+            self.counter = 0;
+        }
+
+        let mut trigger_at = None;
+        let mut capture_start = if self.phase == TriggerPhase::Capturing { Some(0) } else { None };
+        let mut capture_end = None;
+        let mut length = 0;
+        for (index, sample) in source.enumerate() {
+            length = index + 1;
+            match self.phase {
+                TriggerPhase::Armed => {
+                    self.counter += 1;
+                    let crossed = match settings.slope {
+                        TriggerSlope::Rising => self.previous < settings.level && sample >= settings.level,
+                        TriggerSlope::Falling => self.previous > settings.level && sample <= settings.level,
+                    };
+                    let timed_out = settings.mode == TriggerMode::Auto && self.counter >= settings.auto_timeout;
+                    if (crossed || timed_out) && capture_start.is_none() {
+                        trigger_at = Some(index);
+                        capture_start = Some(index);
+                        self.phase = TriggerPhase::Capturing;
+                        self.counter = settings.capture_samples;
+                    }
+                }
+                TriggerPhase::Capturing => {
+                    self.counter = self.counter.saturating_sub(1);
+                    if self.counter == 0 {
+                        capture_end = Some(index);
+                        if settings.mode == TriggerMode::Single {
+                            self.phase = TriggerPhase::Stopped;
+                        } else {
+                            self.phase = TriggerPhase::Holdoff;
+                            self.counter = settings.holdoff_samples;
+                        }
+                    }
+                }
+                TriggerPhase::Holdoff => {
+                    if self.counter == 0 {
+                        self.phase = TriggerPhase::Armed;
+                    } else {
+                        self.counter -= 1;
+                    }
+                }
+                TriggerPhase::Stopped => {}
+            }
+            self.previous = sample;
+        }
+
+        let capture = match capture_start {
+            Some(start) => start..capture_end.unwrap_or(length),
+            None => 0..0,
+        };
+        (trigger_at, capture)
+    }
+}
+
+#[derive(Enum, Clone, Copy, PartialEq)]
+pub enum FftWindow {
+    // No window, the original behavior
+    Rectangular,
+    Hann,
+    Hamming,
+    // 4 term, sidelobes low enough that quiet content next to loud bass notes still shows
+    #[name = "Blackman-Harris"]
+    BlackmanHarris,
+    // Widest main lobe but reads a sine's level right wherever it falls between bins
+    #[name = "Flat Top"]
+    FlatTop,
+}
+
+impl FftWindow {
+    // Cosine sum terms, w(n) = a0 - a1 cos(2 pi n / N) + a2 cos(4 pi n / N) - ...
+    fn terms(self) -> &'static [f32] {
+        match self {
+            FftWindow::Rectangular => &[1.0],
+            FftWindow::Hann => &[0.5, 0.5],
+            FftWindow::Hamming => &[0.54, 0.46],
+            FftWindow::BlackmanHarris => &[0.35875, 0.48829, 0.14128, 0.01168],
+            FftWindow::FlatTop => &[0.21557895, 0.41663158, 0.277263158, 0.083578947, 0.006947368],
+        }
+    }
+
+    // Periodic window of len points with its amplitude correction folded in, so a sine reads the
+    // same level whichever window is picked
+    fn coefficients(self, len: usize) -> Vec<f32> {
+        let terms = self.terms();
+        // A periodic cosine sum averages out to a0, its coherent gain
+        let correction = 1.0 / terms[0];
+        (0..len)
+            .map(|n| {
+                let phase = std::f64::consts::TAU * n as f64 / len as f64;
+                let weight: f64 = terms
+                    .iter()
+                    .enumerate()
+                    .map(|(k, term)| {
+                        let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+                        sign * *term as f64 * (phase * k as f64).cos()
+                    })
+                    .sum();
+                weight as f32 * correction
+            })
+            .collect()
+    }
+
+    // Equivalent noise bandwidth in bins. Summing the power of the bins a sine spreads over reads this
+    // much too high once the amplitude correction is in
+    fn noise_bandwidth(self) -> f32 {
+        let terms = self.terms();
+        let power = terms[0] * terms[0] + terms[1..].iter().map(|term| term * term / 2.0).sum::<f32>();
+        power / (terms[0] * terms[0])
+    }
+}
+
+#[derive(Enum, Clone, Copy, PartialEq)]
+pub enum FftSize {
+    #[name = "1024"]
+    Size1024,
+    #[name = "2048"]
+    Size2048,
+    #[name = "4096"]
+    Size4096,
+    #[name = "8192"]
+    Size8192,
+    #[name = "16384"]
+    Size16384,
+    #[name = "32768"]
+    Size32768,
+    #[name = "65536"]
+    Size65536,
+}
+
+impl FftSize {
+    fn len(self) -> usize {
+        1024 << self.to_index()
+    }
+}
+
+#[derive(Enum, Clone, Copy, PartialEq)]
+pub enum FftOverlap {
+    #[name = "0%"]
+    None,
+    #[name = "50%"]
+    Half,
+    #[name = "75%"]
+    ThreeQuarters,
+    #[name = "87.5%"]
+    SevenEighths,
+}
+
+impl FftOverlap {
+    // Samples between the starts of consecutive FFT frames
+    fn hop(self, fft_len: usize) -> usize {
+        (fft_len >> self.to_index()).max(1)
+    }
+}
+
+#[derive(Enum, Clone, Copy, PartialEq)]
+pub enum SpectrumAveraging {
+    // Every FFT frame as it comes
+    Off,
+    // RC style, recent frames weigh the most
+    Exponential,
+    // Every frame inside the averaging time counts the same
+    Linear,
+}
+
+#[derive(Enum, Clone, Copy, PartialEq)]
+pub enum AnalyzerView {
+    // Level of each input
+    Spectrum,
+    // Response of whatever sits between a reference aux input and main
+    Transfer,
+    // Phase of each input, or of main against a reference aux input
+    Phase,
+}
+
+// What of a stereo input the analyzer looks at
+#[derive(Enum, Clone, Copy, PartialEq)]
+pub enum ChannelMode {
+    Left,
+    Right,
+    #[name = "L+R"]
+    Sum,
+    // Half the sum, a mono fold down at the same level as either side
+    Mid,
+    // Half the difference, what's only on one side
+    Side,
+}
+
+impl ChannelMode {
+    fn mix(self, left: f32, right: f32) -> f32 {
+        match self {
+            ChannelMode::Left => left,
+            ChannelMode::Right => right,
+            ChannelMode::Sum => left + right,
+            ChannelMode::Mid => (left + right) * 0.5,
+            ChannelMode::Side => (left - right) * 0.5,
+        }
+    }
+}
+
+#[derive(Enum, Clone, Copy, PartialEq)]
+pub enum BarBands {
+    // The original 64 equal groups of FFT bins
+    #[name = "64 Bins"]
+    Linear,
+    #[name = "1/1 Oct"]
+    Octave,
+    #[name = "1/3 Oct"]
+    Third,
+    #[name = "1/6 Oct"]
+    Sixth,
+    #[name = "1/12 Oct"]
+    Twelfth,
+}
+
+impl BarBands {
+    fn bands_per_octave(self) -> Option<u32> {
+        match self {
+            BarBands::Linear => None,
+            BarBands::Octave => Some(1),
+            BarBands::Third => Some(3),
+            BarBands::Sixth => Some(6),
+            BarBands::Twelfth => Some(12),
+        }
+    }
+}
+
+#[derive(Enum, Clone, Copy, PartialEq)]
+pub enum TiltSlope {
+    #[name = "0 dB/oct"]
+    Flat,
+    // Pink noise reads flat
+    #[name = "3 dB/oct"]
+    Three,
+    #[name = "4.5 dB/oct"]
+    FourHalf,
+    // Brown noise reads flat
+    #[name = "6 dB/oct"]
+    Six,
+}
+
+impl TiltSlope {
+    fn db_per_octave(self) -> f32 {
+        match self {
+            TiltSlope::Flat => 0.0,
+            TiltSlope::Three => 3.0,
+            TiltSlope::FourHalf => 4.5,
+            TiltSlope::Six => 6.0,
+        }
+    }
+}
+
+// Slope and pivot pairs offered as presets in the analyzer
+const TILT_PRESETS: [(&str, TiltSlope, f32); 4] = [
+    ("Flat", TiltSlope::Flat, 1000.0),
+    ("Pink, 3 dB/oct at 1 kHz", TiltSlope::Three, 1000.0),
+    ("Mastering, 4.5 dB/oct at 1 kHz (SPAN, Pro-Q, Parametric EQ 2)", TiltSlope::FourHalf, 1000.0),
+    ("Brown, 6 dB/oct at 1 kHz", TiltSlope::Six, 1000.0),
+];
+
+#[derive(Enum, Clone, Copy, PartialEq)]
+pub enum Colormap {
+    Magma,
+    Viridis,
+    // Black through red and yellow to white
+    Heat,
+    Grayscale,
+}
+
+impl Colormap {
+    // Colors from the bottom of the dB range to the top, evenly spaced
+    fn stops(self) -> &'static [[u8; 3]] {
+        match self {
+            Colormap::Magma => &[
+                [0, 0, 4], [28, 16, 68], [79, 18, 123], [129, 37, 129], [181, 54, 122],
+                [229, 80, 100], [251, 135, 97], [254, 194, 135], [252, 253, 191],
+            ],
+            Colormap::Viridis => &[
+                [68, 1, 84], [72, 40, 120], [62, 74, 137], [49, 104, 142], [38, 130, 142],
+                [31, 158, 137], [53, 183, 121], [110, 206, 88], [181, 222, 43], [253, 231, 37],
+            ],
+            Colormap::Heat => &[[0, 0, 0], [128, 0, 0], [255, 64, 0], [255, 200, 0], [255, 255, 255]],
+            Colormap::Grayscale => &[[0, 0, 0], [255, 255, 255]],
+        }
+    }
 }
 
 pub struct Scrollscope {
@@ -198,39 +929,34 @@ pub struct Scrollscope {
 
     // Counter for scaling sample skipping - use a local counter to reduce atomic ops
     skip_counter: [Arc<AtomicI32>; 2],
-    focused_line_toggle: Arc<AtomicU8>,
+    // Partially filled decimation buckets carried between blocks (left, right)
+    envelope_buckets: [[EnvelopeBucket; NUM_CHANNELS]; 2],
     is_clipping: Arc<AtomicF32>,
-    direction: Arc<AtomicBool>,
+    // Freeze the capture so the history can be scrubbed
+    hold: Arc<AtomicBool>,
+    // Level trigger state and the editor's request to arm a Single capture again
+    trigger_state: TriggerState,
+    trigger_rearm: Arc<AtomicBool>,
     
-    // Channel visibility flags
-    channel_enabled: [Arc<AtomicBool>; 7], // main + 5 aux + sum
-
     // Gui flags
-    enable_sum: Arc<AtomicBool>,
     enable_guidelines: Arc<AtomicBool>,
     enable_bar_mode: Arc<AtomicBool>,
     
     // Data holding values - optimized buffer implementation
     sample_buffer: Arc<OptimizedBuffer>,
     sample_buffer_2: Arc<OptimizedBuffer>,
+    // Long view envelopes and the audio thread's unfinished buckets for them (left, right)
+    envelope_store: Arc<EnvelopeStore>,
+    envelope_store_2: Arc<EnvelopeStore>,
+    envelope_cascade: [EnvelopeCascade; 2],
 
     // Syncing for beats - cached to reduce atomic ops
-    sync_var: Arc<AtomicBool>,
-    alt_sync: Arc<AtomicBool>,
     in_place_index: Arc<AtomicI32>,
     beat_threshold: Arc<AtomicI32>,
     add_beat_line: Arc<AtomicBool>,
 
     // FFT/Analyzer
     fft: Arc<Mutex<FftPlanner<f32>>>,
-    show_analyzer: Arc<AtomicBool>,
-    en_filled_lines: Arc<AtomicBool>,
-    en_filled_osc: Arc<AtomicBool>,
-
-    // Stereo view
-    stereo_view: Arc<AtomicBool>,
-    en_left_channel: Arc<AtomicBool>,
-    en_right_channel: Arc<AtomicBool>,
 
     sample_rate: Arc<AtomicF32>,
     prev_skip: Arc<AtomicI32>,
@@ -254,59 +980,216 @@ struct ScrollscopeParams {
     #[id = "scrollspeed"]
     pub scrollspeed: FloatParam,
 
+    /// Windows past MAX_DISPLAY_MS, drawn from the envelope mipmap
+    #[id = "long_view"]
+    pub long_view: BoolParam,
+    #[id = "long_length"]
+    pub long_length: FloatParam,
+
     /// Horizontal Scaling
     #[id = "scaling"]
     pub h_scale: IntParam,
 
+    /// How samples are reduced when scaling
+    #[id = "decimation"]
+    pub decimation: EnumParam<Decimation>,
+
     /// Sync Timing
     #[id = "Sync Timing"]
     pub sync_timing: EnumParam<BeatSync>,
+
+    /// Level trigger
+    #[id = "trigger_mode"]
+    pub trigger_mode: EnumParam<TriggerMode>,
+    #[id = "trigger_source"]
+    pub trigger_source: EnumParam<InputChannel>,
+    #[id = "trigger_level"]
+    pub trigger_level: FloatParam,
+    #[id = "trigger_slope"]
+    pub trigger_slope: EnumParam<TriggerSlope>,
+    #[id = "trigger_holdoff"]
+    pub trigger_holdoff: FloatParam,
+    #[id = "trigger_pre"]
+    pub trigger_pre: FloatParam,
+
+    /// Channel visibility
+    #[id = "en_main"]
+    pub en_main: BoolParam,
+    #[id = "en_aux1"]
+    pub en_aux1: BoolParam,
+    #[id = "en_aux2"]
+    pub en_aux2: BoolParam,
+    #[id = "en_aux3"]
+    pub en_aux3: BoolParam,
+    #[id = "en_aux4"]
+    pub en_aux4: BoolParam,
+    #[id = "en_aux5"]
+    pub en_aux5: BoolParam,
+    #[id = "en_sum"]
+    pub en_sum: BoolParam,
+
+    /// Side of each input the analyzer looks at
+    #[id = "mode_main"]
+    pub mode_main: EnumParam<ChannelMode>,
+    #[id = "mode_aux1"]
+    pub mode_aux1: EnumParam<ChannelMode>,
+    #[id = "mode_aux2"]
+    pub mode_aux2: EnumParam<ChannelMode>,
+    #[id = "mode_aux3"]
+    pub mode_aux3: EnumParam<ChannelMode>,
+    #[id = "mode_aux4"]
+    pub mode_aux4: EnumParam<ChannelMode>,
+    #[id = "mode_aux5"]
+    pub mode_aux5: EnumParam<ChannelMode>,
+
+    /// Stereo view and its sides
+    #[id = "stereo_view"]
+    pub stereo_view: BoolParam,
+    #[id = "en_left"]
+    pub en_left_channel: BoolParam,
+    #[id = "en_right"]
+    pub en_right_channel: BoolParam,
+
+    /// Line drawn in front of the others
+    #[id = "focused_line"]
+    pub focused_line: EnumParam<InputChannel>,
+
+    /// Beat sync
+    #[id = "sync"]
+    pub sync: BoolParam,
+    #[id = "alt_sync"]
+    pub alt_sync: BoolParam,
+
+    /// Flip the scroll direction
+    #[id = "flip"]
+    pub flip: BoolParam,
+
+    /// Fill the oscilloscope lines
+    #[id = "fill"]
+    pub fill: BoolParam,
+
+    /// Analyzer and its fill
+    #[id = "show_analyzer"]
+    pub show_analyzer: BoolParam,
+    #[id = "filled_lines"]
+    pub filled_lines: BoolParam,
+    #[id = "analyzer_view"]
+    pub analyzer_view: EnumParam<AnalyzerView>,
+    #[id = "fft_window"]
+    pub fft_window: EnumParam<FftWindow>,
+    #[id = "fft_size"]
+    pub fft_size: EnumParam<FftSize>,
+    #[id = "fft_overlap"]
+    pub fft_overlap: EnumParam<FftOverlap>,
+
+    /// Analyzer averaging and peak hold
+    #[id = "spectrum_averaging"]
+    pub spectrum_averaging: EnumParam<SpectrumAveraging>,
+    #[id = "average_time"]
+    pub average_time: FloatParam,
+    #[id = "peak_hold"]
+    pub peak_hold: BoolParam,
+    #[id = "peak_hold_time"]
+    pub peak_hold_time: FloatParam,
+    #[id = "peak_release"]
+    pub peak_release: FloatParam,
+    #[id = "peak_labels"]
+    pub peak_labels: IntParam,
+    #[id = "bar_bands"]
+    pub bar_bands: EnumParam<BarBands>,
+
+    /// Masking between two inputs
+    #[id = "masking"]
+    pub masking: BoolParam,
+    #[id = "masking_a"]
+    pub masking_a: EnumParam<InputChannel>,
+    #[id = "masking_b"]
+    pub masking_b: EnumParam<InputChannel>,
+    #[id = "masking_threshold"]
+    pub masking_threshold: FloatParam,
+    #[id = "masking_range"]
+    pub masking_range: FloatParam,
+
+    /// Transfer function from a reference input to main, the phase difference uses the same reference
+    #[id = "transfer_reference"]
+    pub transfer_reference: EnumParam<InputChannel>,
+    #[id = "transfer_range"]
+    pub transfer_range: FloatParam,
+
+    /// Phase view
+    #[id = "phase_unwrap"]
+    pub phase_unwrap: BoolParam,
+    #[id = "phase_difference"]
+    pub phase_difference: BoolParam,
+
+    /// Analyzer tilt
+    #[id = "tilt_slope"]
+    pub tilt_slope: EnumParam<TiltSlope>,
+    #[id = "tilt_pivot"]
+    pub tilt_pivot: FloatParam,
+
+    /// Analyzer frequency and dB ranges, also set by zooming and dragging on the analyzer
+    #[id = "analyzer_freq_min"]
+    pub analyzer_freq_min: FloatParam,
+    #[id = "analyzer_freq_max"]
+    pub analyzer_freq_max: FloatParam,
+    #[id = "analyzer_db_min"]
+    pub analyzer_db_min: FloatParam,
+    #[id = "analyzer_db_max"]
+    pub analyzer_db_max: FloatParam,
+
+    /// Spectrogram, shares the FFT settings, tilt and ranges with the analyzer
+    #[id = "show_spectrogram"]
+    pub show_spectrogram: BoolParam,
+    #[id = "spectrogram_source"]
+    pub spectrogram_source: EnumParam<InputChannel>,
+    #[id = "spectrogram_colormap"]
+    pub spectrogram_colormap: EnumParam<Colormap>,
+    #[id = "spectrogram_history"]
+    pub spectrogram_history: FloatParam,
+
+    // The scope's h_scale, put back when leaving the analyzer or spectrogram
+    #[persist = "prev-skip"]
+    pub prev_skip: Arc<AtomicI32>,
+    #[persist = "guidelines"]
+    pub enable_guidelines: Arc<AtomicBool>,
+    #[persist = "bar-mode"]
+    pub enable_bar_mode: Arc<AtomicBool>,
+    // Snapshots of the analyzer drawn behind it, saved with the project
+    #[persist = "references"]
+    pub references: RwLock<Vec<analyzer::ReferenceSpectrum>>,
 }
 
 impl Default for Scrollscope {
     fn default() -> Self {
         // Initialize with reasonable defaults based on 44.1kHz
-        let initial_buffer_size = calculate_buffer_size(44100.0, MAX_DISPLAY_MS);
+        let initial_buffer_size = calculate_buffer_size(44100.0, MAX_DISPLAY_MS + HISTORY_MS);
+        let params = Arc::new(ScrollscopeParams::default());
         
         Self {
-            params: Arc::new(ScrollscopeParams::default()),
+            params: params.clone(),
             skip_counter: [Arc::new(AtomicI32::new(0)), Arc::new(AtomicI32::new(0))],
-            focused_line_toggle: Arc::new(AtomicU8::new(0)),
-            direction: Arc::new(AtomicBool::new(false)),
+            envelope_buckets: [[EnvelopeBucket::EMPTY; NUM_CHANNELS]; 2],
+            hold: Arc::new(AtomicBool::new(false)),
+            trigger_state: TriggerState::IDLE,
+            trigger_rearm: Arc::new(AtomicBool::new(false)),
             is_clipping: Arc::new(AtomicF32::new(0.0)),
             
-            // Replace individual AtomicBools with an array for simpler access
-            channel_enabled: [
-                Arc::new(AtomicBool::new(true)),  // main
-                Arc::new(AtomicBool::new(false)), // aux_1
-                Arc::new(AtomicBool::new(false)), // aux_2
-                Arc::new(AtomicBool::new(false)), // aux_3
-                Arc::new(AtomicBool::new(false)), // aux_4
-                Arc::new(AtomicBool::new(false)), // aux_5
-                Arc::new(AtomicBool::new(true)),  // sum
-            ],
-            en_left_channel: Arc::new(AtomicBool::new(true)),
-            en_right_channel: Arc::new(AtomicBool::new(true)),
-
-            enable_sum: Arc::new(AtomicBool::new(true)),
-            enable_guidelines: Arc::new(AtomicBool::new(true)),
-            enable_bar_mode: Arc::new(AtomicBool::new(false)),
+            enable_guidelines: params.enable_guidelines.clone(),
+            enable_bar_mode: params.enable_bar_mode.clone(),
             
             sample_buffer: Arc::new(OptimizedBuffer::new(initial_buffer_size)),
             sample_buffer_2: Arc::new(OptimizedBuffer::new(initial_buffer_size)),
+            envelope_store: Arc::new(EnvelopeStore::new()),
+            envelope_store_2: Arc::new(EnvelopeStore::new()),
+            envelope_cascade: [EnvelopeCascade::EMPTY; 2],
 
-            sync_var: Arc::new(AtomicBool::new(false)),
-            alt_sync: Arc::new(AtomicBool::new(false)),
             add_beat_line: Arc::new(AtomicBool::new(false)),
             in_place_index: Arc::new(AtomicI32::new(0)),
             beat_threshold: Arc::new(AtomicI32::new(0)),
             fft: Arc::new(Mutex::new(FftPlanner::new())),
-            show_analyzer: Arc::new(AtomicBool::new(false)),
-            en_filled_lines: Arc::new(AtomicBool::new(false)),
-            en_filled_osc: Arc::new(AtomicBool::new(false)),
-            stereo_view: Arc::new(AtomicBool::new(false)),
             sample_rate: Arc::new(AtomicF32::new(44100.0)),
-            prev_skip: Arc::new(AtomicI32::new(24)),
+            prev_skip: params.prev_skip.clone(),
             
             // Cache parameters for faster access
             gain_cache: Arc::new(AtomicF32::new(1.0)),
@@ -336,20 +1219,168 @@ impl Default for ScrollscopeParams {
             .with_string_to_value(formatters::s2v_f32_gain_to_db()),
 
             // scrollspeed parameter
-            scrollspeed: FloatParam::new("Length", 100.0, FloatRange::Skewed { min: 1.0, max: 1000.0, factor: 0.33 })
+            scrollspeed: FloatParam::new("Length", 100.0, FloatRange::Skewed { min: 1.0, max: MAX_DISPLAY_MS, factor: 0.33 })
                 .with_unit(" ms")
                 .with_step_size(1.0),
 
+            // Long view, its own length so Length keeps its range for saved sessions and automation
+            long_view: BoolParam::new("Long View", false),
+            long_length: FloatParam::new("Long Length", 8.0, FloatRange::Skewed { min: MAX_DISPLAY_MS / 1000.0, max: MAX_LONG_DISPLAY_MS / 1000.0, factor: FloatRange::skew_factor(-2.0) })
+                .with_unit(" s")
+                .with_step_size(0.1),
+
             // scaling parameter
             h_scale: IntParam::new("Scale", 24, IntRange::Linear { min: 1, max: 100 })
                 .with_unit(" Skip"),
 
+            // Decimation mode for the scaling parameter
+            decimation: EnumParam::new("Decimation", Decimation::Skip),
+
             // Sync timing parameter
             sync_timing: EnumParam::new("Timing", BeatSync::Beat),
+
+            // Level trigger
+            trigger_mode: EnumParam::new("Trigger", TriggerMode::Off),
+            trigger_source: EnumParam::new("Trigger Source", InputChannel::Main),
+            trigger_level: FloatParam::new("Trigger Level", 0.0, FloatRange::Linear { min: -1.0, max: 1.0 })
+                .with_step_size(0.01),
+            trigger_slope: EnumParam::new("Slope", TriggerSlope::Rising),
+            trigger_holdoff: FloatParam::new("Holdoff", 0.0, FloatRange::Skewed { min: 0.0, max: 1000.0, factor: 0.33 })
+                .with_unit(" ms")
+                .with_step_size(1.0),
+            trigger_pre: FloatParam::new("Pre-Trigger", 10.0, FloatRange::Linear { min: 0.0, max: 100.0 })
+                .with_unit("%")
+                .with_step_size(1.0),
+
+            // Channel visibility
+            en_main: BoolParam::new("Show Main", true),
+            en_aux1: BoolParam::new("Show Aux 1", false),
+            en_aux2: BoolParam::new("Show Aux 2", false),
+            en_aux3: BoolParam::new("Show Aux 3", false),
+            en_aux4: BoolParam::new("Show Aux 4", false),
+            en_aux5: BoolParam::new("Show Aux 5", false),
+            en_sum: BoolParam::new("Show Sum", true),
+
+            // Analyzer channel modes, left is what the analyzer always looked at
+            mode_main: EnumParam::new("Main Mode", ChannelMode::Left),
+            mode_aux1: EnumParam::new("Aux 1 Mode", ChannelMode::Left),
+            mode_aux2: EnumParam::new("Aux 2 Mode", ChannelMode::Left),
+            mode_aux3: EnumParam::new("Aux 3 Mode", ChannelMode::Left),
+            mode_aux4: EnumParam::new("Aux 4 Mode", ChannelMode::Left),
+            mode_aux5: EnumParam::new("Aux 5 Mode", ChannelMode::Left),
+
+            // Stereo view
+            stereo_view: BoolParam::new("Stereo View", false),
+            en_left_channel: BoolParam::new("Show Left", true),
+            en_right_channel: BoolParam::new("Show Right", true),
+
+            focused_line: EnumParam::new("Focused Line", InputChannel::Main),
+
+            sync: BoolParam::new("Sync", false),
+            alt_sync: BoolParam::new("Alt Sync", false),
+            flip: BoolParam::new("Flip", false),
+            fill: BoolParam::new("Fill", false),
+
+            show_analyzer: BoolParam::new("Analyze", false),
+            filled_lines: BoolParam::new("Filled Lines", false),
+            analyzer_view: EnumParam::new("View", AnalyzerView::Spectrum),
+            fft_window: EnumParam::new("Window", FftWindow::Hann),
+            fft_size: EnumParam::new("FFT Size", FftSize::Size8192),
+            fft_overlap: EnumParam::new("Overlap", FftOverlap::Half),
+
+            spectrum_averaging: EnumParam::new("Averaging", SpectrumAveraging::Exponential),
+            average_time: FloatParam::new("Avg Time", 250.0, FloatRange::Skewed { min: 10.0, max: 10000.0, factor: 0.3 })
+                .with_unit(" ms")
+                .with_step_size(1.0),
+            peak_hold: BoolParam::new("Peak Hold", false),
+            peak_hold_time: FloatParam::new("Hold Time", 1000.0, FloatRange::Skewed { min: 0.0, max: 10000.0, factor: 0.3 })
+                .with_unit(" ms")
+                .with_step_size(1.0),
+            peak_release: FloatParam::new("Release", 12.0, FloatRange::Linear { min: 0.0, max: 96.0 })
+                .with_unit(" dB/s")
+                .with_step_size(0.5),
+            peak_labels: IntParam::new("Peak Labels", 0, IntRange::Linear { min: 0, max: 8 })
+                .with_value_to_string(Arc::new(|count| if count == 0 { String::from("Off") } else { format!("{} Peaks", count) })),
+            bar_bands: EnumParam::new("Bands", BarBands::Third),
+
+            masking: BoolParam::new("Masking", false),
+            masking_a: EnumParam::new("Masking A", InputChannel::Main),
+            masking_b: EnumParam::new("Masking B", InputChannel::Aux1),
+            masking_threshold: FloatParam::new("Threshold", -50.0, FloatRange::Linear { min: ANALYZER_DB_LIMITS.0, max: 0.0 })
+                .with_unit(" dB")
+                .with_step_size(0.5),
+            masking_range: FloatParam::new("Within", 6.0, FloatRange::Linear { min: 1.0, max: 24.0 })
+                .with_unit(" dB")
+                .with_step_size(0.5),
+
+            transfer_reference: EnumParam::new("Reference", InputChannel::Aux1),
+            transfer_range: FloatParam::new("Gain Range", 24.0, FloatRange::Linear { min: 3.0, max: 60.0 })
+                .with_unit(" dB")
+                .with_step_size(1.0),
+
+            phase_unwrap: BoolParam::new("Unwrap", false),
+            phase_difference: BoolParam::new("Difference", false),
+
+            tilt_slope: EnumParam::new("Tilt", TiltSlope::FourHalf),
+            tilt_pivot: FloatParam::new("Pivot", 1000.0, FloatRange::Skewed { min: 20.0, max: 20000.0, factor: FloatRange::skew_factor(-2.0) })
+                .with_value_to_string(formatters::v2s_f32_hz_then_khz(1))
+                .with_string_to_value(formatters::s2v_f32_hz_then_khz()),
+
+            analyzer_freq_min: FloatParam::new("Min Freq", 20.0, analyzer_freq_range())
+                .with_value_to_string(formatters::v2s_f32_hz_then_khz(1))
+                .with_string_to_value(formatters::s2v_f32_hz_then_khz()),
+            analyzer_freq_max: FloatParam::new("Max Freq", 20000.0, analyzer_freq_range())
+                .with_value_to_string(formatters::v2s_f32_hz_then_khz(1))
+                .with_string_to_value(formatters::s2v_f32_hz_then_khz()),
+            analyzer_db_min: FloatParam::new("Min dB", -96.0, FloatRange::Linear { min: ANALYZER_DB_LIMITS.0, max: ANALYZER_DB_LIMITS.1 })
+                .with_unit(" dB")
+                .with_step_size(0.5),
+            analyzer_db_max: FloatParam::new("Max dB", 6.0, FloatRange::Linear { min: ANALYZER_DB_LIMITS.0, max: ANALYZER_DB_LIMITS.1 })
+                .with_unit(" dB")
+                .with_step_size(0.5),
+
+            show_spectrogram: BoolParam::new("Spectrogram", false),
+            spectrogram_source: EnumParam::new("Source", InputChannel::Main),
+            spectrogram_colormap: EnumParam::new("Colormap", Colormap::Magma),
+            spectrogram_history: FloatParam::new("History", 5.0, FloatRange::Linear { min: 1.0, max: 30.0 })
+                .with_unit(" s")
+                .with_step_size(0.5),
+
+            prev_skip: Arc::new(AtomicI32::new(24)),
+            enable_guidelines: Arc::new(AtomicBool::new(true)),
+            enable_bar_mode: Arc::new(AtomicBool::new(false)),
+            references: RwLock::new(Vec::new()),
         }
     }
 }
 
+// Both ends of the analyzer's frequency range share the whole span so either can go anywhere
+fn analyzer_freq_range() -> FloatRange {
+    FloatRange::Skewed { min: ANALYZER_FREQ_LIMITS.0, max: ANALYZER_FREQ_LIMITS.1, factor: FloatRange::skew_factor(-2.0) }
+}
+
+impl ScrollscopeParams {
+    // Time the scope spans in ms, Length or the long view's length. The sample ring only ever
+    // holds Length, so the Hold history behind it doesn't shrink in the long view
+    fn display_ms(&self) -> f32 {
+        if self.long_view.value() {
+            self.long_length.value() * 1000.0
+        } else {
+            self.scrollspeed.value()
+        }
+    }
+
+    // Visibility of the main input and the 5 aux inputs, in channel order
+    fn input_channels(&self) -> [&BoolParam; 6] {
+        [&self.en_main, &self.en_aux1, &self.en_aux2, &self.en_aux3, &self.en_aux4, &self.en_aux5]
+    }
+
+    // Analyzer channel mode of each input, in the same order
+    fn channel_modes(&self) -> [&EnumParam<ChannelMode>; 6] {
+        [&self.mode_main, &self.mode_aux1, &self.mode_aux2, &self.mode_aux3, &self.mode_aux4, &self.mode_aux5]
+    }
+}
+
 impl Plugin for Scrollscope {
     const NAME: &'static str = "Scrollscope";
     const VENDOR: &'static str = "Ardura";
@@ -411,6 +1442,9 @@ impl Plugin for Scrollscope {
         
         // Calculate appropriate buffer size based on sample rate and max display time
         let buffer_size = calculate_buffer_size(sample_rate, MAX_DISPLAY_MS);
+        let history_size = calculate_buffer_size(sample_rate, MAX_DISPLAY_MS + HISTORY_MS);
+        self.sample_buffer.reserve(history_size);
+        self.sample_buffer_2.reserve(history_size);
         self.sample_buffer.update_internal_length(buffer_size);
         self.sample_buffer_2.update_internal_length(buffer_size);
         
@@ -428,6 +1462,11 @@ impl Plugin for Scrollscope {
             return ProcessStatus::Normal;
         }
 
+        // Hold freezes the capture - audio still passes through untouched
+        if self.hold.load(Ordering::Relaxed) {
+            return ProcessStatus::Normal;
+        }
+
         // Update cached parameters to reduce atomic loads inside the loop
         let current_gain = self.params.free_gain.smoothed.next();
         self.gain_cache.store(current_gain, Ordering::Relaxed);
@@ -440,7 +1479,7 @@ impl Plugin for Scrollscope {
         if sample_rate != self.sample_rate.load(Ordering::Relaxed) {
             self.sample_rate.store(sample_rate, Ordering::Release);
             
-            // Recalculate buffer size based on new sample rate
+            // Recalculate buffer size based on new sample rate - storage was already sized in initialize()
             let buffer_size = calculate_buffer_size(sample_rate, self.params.scrollspeed.value());
             self.sample_buffer.update_internal_length(buffer_size);
             self.sample_buffer_2.update_internal_length(buffer_size);
@@ -450,15 +1489,29 @@ impl Plugin for Scrollscope {
         let mut local_skip_counter = [0,0];
         self.skip_counter[0].store(0, Ordering::Relaxed);
         self.skip_counter[1].store(0, Ordering::Relaxed);
+        let mut envelope_buckets = self.envelope_buckets;
+        let mut envelope_cascade = self.envelope_cascade;
+        let mut trigger_state = self.trigger_state;
 
-        // Determine whether to process in analyzer mode or oscilloscope mode
-        if !self.show_analyzer.load(Ordering::Relaxed) {
+        // Determine whether to process in analyzer mode or oscilloscope mode, the spectrogram reads what the analyzer does
+        if !self.params.show_analyzer.value() && !self.params.show_spectrogram.value() {
             // Process in oscilloscope mode
-            self.process_oscilloscope(buffer, aux, context, &mut local_skip_counter);
+            self.process_oscilloscope(
+                buffer,
+                aux,
+                context,
+                &mut local_skip_counter,
+                &mut envelope_buckets,
+                &mut envelope_cascade,
+                &mut trigger_state,
+            );
         } else {
             // Process in analyzer mode
-            self.process_analyzer(buffer, aux, &mut local_skip_counter);
+            self.process_analyzer(buffer, aux, context, &mut local_skip_counter, &mut envelope_buckets);
         }
+        self.envelope_buckets = envelope_buckets;
+        self.envelope_cascade = envelope_cascade;
+        self.trigger_state = trigger_state;
         
         // Update the skip counter
         self.skip_counter[0].store(local_skip_counter[0], Ordering::Relaxed);
@@ -488,7 +1541,15 @@ impl Scrollscope {
         aux: &mut nih_plug::audio_setup::AuxiliaryBuffers<'_>,
         context: &mut impl ProcessContext<Self>,
         skip_counter: &mut [i32; 2],
+        envelope_buckets: &mut [[EnvelopeBucket; NUM_CHANNELS]; 2],
+        envelope_cascade: &mut [EnvelopeCascade; 2],
+        trigger_state: &mut TriggerState,
     ) {
+        // Writers are taken once per block - if initialize() is resizing we just skip drawing this block
+        let (Some(left_writer), Some(right_writer)) = (self.sample_buffer.writer(), self.sample_buffer_2.writer()) else {
+            return;
+        };
+
         // Get buffer slices for efficient processing
         let raw_buffer = buffer.as_slice_immutable();
         let aux_0 = aux.inputs[0].as_slice_immutable();
@@ -500,304 +1561,306 @@ impl Scrollscope {
         // Cache parameters to avoid atomic loads in the loop
         let h_scale = self.h_scale_cache.load(Ordering::Relaxed) as i32;
         let current_gain = self.gain_cache.load(Ordering::Relaxed);
-        let sync_active = self.sync_var.load(Ordering::Relaxed);
-        let alt_sync_active = self.alt_sync.load(Ordering::Relaxed);
-        let stereo_mode = self.stereo_view.load(Ordering::Relaxed);
+        let trigger_mode = self.params.trigger_mode.value();
+        let trigger_active = trigger_mode != TriggerMode::Off;
+        // The trigger takes over the in-place window from Sync
+        let sync_active = self.params.sync.value() && !trigger_active;
+        let alt_sync_active = self.params.alt_sync.value();
+        let stereo_mode = self.params.stereo_view.value();
+        let decimation = self.params.decimation.value();
+        let sum_active = self.params.en_sum.value();
+        let sum_inputs = self.params.input_channels().map(|param| param.value());
+        let beat_height = if stereo_mode { 2.1 } else { 1.0 };
         
         // Process beat detection once per buffer instead of per sample
-        let (is_on_beat, is_on_bar) = self.detect_beat(context);
-        let mut add_beat_line = false;
+        let (is_on_beat, is_on_division) = self.detect_beat(context);
         
         // Update in-place index if needed
         let mut in_place_idx = self.in_place_index.load(Ordering::Relaxed);
         if sync_active {
             if alt_sync_active {
                 if context.transport().playing {
-                    match self.params.sync_timing.value() {
-                        BeatSync::Bar => {
-                            if is_on_bar && self.beat_threshold.load(Ordering::Relaxed) == 0 {
-                                in_place_idx = 0;
-                                self.beat_threshold.fetch_add(1, Ordering::Relaxed);
-                            } else if !is_on_bar && self.beat_threshold.load(Ordering::Relaxed) > 0 {
-                                self.beat_threshold.store(0, Ordering::Relaxed);
-                            }
-                        },
-                        BeatSync::Beat => {
-                            if is_on_beat && self.beat_threshold.load(Ordering::Relaxed) == 0 {
-                                in_place_idx = 0;
-                                self.beat_threshold.fetch_add(1, Ordering::Relaxed);
-                            } else if !is_on_beat && self.beat_threshold.load(Ordering::Relaxed) > 0 {
-                                self.beat_threshold.store(0, Ordering::Relaxed);
-                            }
-                        }
+                    if is_on_division && self.beat_threshold.load(Ordering::Relaxed) == 0 {
+                        in_place_idx = 0;
+                        self.beat_threshold.fetch_add(1, Ordering::Relaxed);
+                    } else if !is_on_division && self.beat_threshold.load(Ordering::Relaxed) > 0 {
+                        self.beat_threshold.store(0, Ordering::Relaxed);
                     }
                 } else {
                     in_place_idx = 0;
                 }
-            } else {
-                // Normal sync mode
-                let current_beat = context.transport().pos_beats().unwrap();
-                let temp_current_beat = (current_beat * 1000.0).round() / 1000.0;
-                
-                const EPSILON: f64 = 0.001;
-                match self.params.sync_timing.value() {
-                    BeatSync::Bar => {
-                        if (temp_current_beat % 4.0) < EPSILON {
-                            in_place_idx = 0;
-                            skip_counter[0] = 0;
-                            skip_counter[1] = 0;
-                            let mut buffers = self.sample_buffer.buffers.write();
-                            for buff in buffers.iter_mut() {
-                                buff[0].zero_out();
-                                buff[1].zero_out();
-                                buff[2].zero_out();
-                                buff[3].zero_out();
-                                buff[4].zero_out();
-                                buff[5].zero_out();
-                                buff[6].zero_out();
-                                buff[7].zero_out();
-                            }
-                            let mut buffers = self.sample_buffer_2.buffers.write();
-                            for buff in buffers.iter_mut() {
-                                buff[0].zero_out();
-                                buff[1].zero_out();
-                                buff[2].zero_out();
-                                buff[3].zero_out();
-                                buff[4].zero_out();
-                                buff[5].zero_out();
-                                buff[6].zero_out();
-                                buff[7].zero_out();
-                            }
-                        }
-                    }
-                    BeatSync::Beat => {
-                        if (temp_current_beat % 1.0) < EPSILON {
-                            in_place_idx = 0;
-                            skip_counter[0] = 0;
-                            skip_counter[1] = 0;
-                            let mut buffers = self.sample_buffer.buffers.write();
-                            for buff in buffers.iter_mut() {
-                                buff[0].zero_out();
-                                buff[1].zero_out();
-                                buff[2].zero_out();
-                                buff[3].zero_out();
-                                buff[4].zero_out();
-                                buff[5].zero_out();
-                                buff[6].zero_out();
-                                buff[7].zero_out();
-                            }
-                            let mut buffers = self.sample_buffer_2.buffers.write();
-                            for buff in buffers.iter_mut() {
-                                buff[0].zero_out();
-                                buff[1].zero_out();
-                                buff[2].zero_out();
-                                buff[3].zero_out();
-                                buff[4].zero_out();
-                                buff[5].zero_out();
-                                buff[6].zero_out();
-                                buff[7].zero_out();
-                            }
-                        }
-                    }
-                }
             }
+            // Normal sync restarts the window inside the sample loop below
+            left_writer.pin_window();
+            right_writer.pin_window();
         }
         
-        // Check if we need to add beat lines
-        if context.transport().playing {
-            if alt_sync_active {
-                add_beat_line = is_on_beat;
-                self.in_place_index.store(0, Ordering::SeqCst);
-            } else if ((context.transport().pos_beats().unwrap() * 1000.0).round() / 1000.0) % 1.0 == 0.0 {
-                add_beat_line = true;
-                if sync_active {
-                    self.in_place_index.store(0, Ordering::SeqCst);
-                }
+        // Alt sync only trusts the transport once per block, so its beat lines land on the first sample
+        let add_beat_line = context.transport().playing && alt_sync_active && is_on_beat;
+
+        // Everywhere else beats and sync windows are placed on the exact sample they fall on in this block
+        let (beat_clock, division_clock) = match (context.transport().tempo, meter_position(context.transport())) {
+            (Some(tempo), Some(meter)) if context.transport().playing && !alt_sync_active => {
+                let samples_per_quarter = 60.0 / tempo * context.transport().sample_rate as f64;
+                let beat_clock = BoundaryClock::new(meter.in_beat, meter.beat_length, samples_per_quarter);
+                let division_clock = if sync_active {
+                    let (position, length) = self.params.sync_timing.value().window(&meter);
+                    BoundaryClock::new(position, length, samples_per_quarter)
+                } else {
+                    BoundaryClock::IDLE
+                };
+                (beat_clock, division_clock)
             }
-        }
+            _ => (BoundaryClock::IDLE, BoundaryClock::IDLE),
+        };
         
+        // Level trigger - find where it fires in this block before either side gets written
+        let window_len = left_writer.window_len();
+        let pre_points = if trigger_active {
+            ((window_len as f32 * self.params.trigger_pre.value() / 100.0) as usize).min(left_writer.capacity() - window_len)
+        } else {
+            0
+        };
+        let (trigger_at, capture) = if trigger_active {
+            left_writer.pin_window();
+            right_writer.pin_window();
+            let trigger_source = self.params.trigger_source.value();
+            let source = match trigger_source {
+                InputChannel::Main => raw_buffer,
+                InputChannel::Aux1 => aux_0,
+                InputChannel::Aux2 => aux_1,
+                InputChannel::Aux3 => aux_2,
+                InputChannel::Aux4 => aux_3,
+                InputChannel::Aux5 => aux_4,
+            };
+            let settings = TriggerSettings {
+                mode: trigger_mode,
+                slope: self.params.trigger_slope.value(),
+                level: self.params.trigger_level.value(),
+                capture_samples: (window_len - pre_points) * h_scale as usize,
+                holdoff_samples: (self.params.trigger_holdoff.value() * self.sample_rate.load(Ordering::Relaxed) / 1000.0) as usize,
+                auto_timeout: window_len * h_scale as usize,
+                rearm: self.trigger_rearm.swap(false, Ordering::Relaxed),
+            };
+            // Trigger on what the scope draws - an aux that mirrors the main input reads as silence there
+            let source_samples = source[0].iter().zip(raw_buffer[0].iter()).map(|(value, main)| {
+                if trigger_source != InputChannel::Main && value == main { 0.0 } else { value * current_gain }
+            });
+            trigger_state.scan(source_samples, &settings)
+        } else {
+            (None, 0..0)
+        };
+        let mut next_pre_index = trigger_state.pre_index;
+        let mut next_capture_index = trigger_state.capture_index;
+        let mut next_in_place_idx = in_place_idx;
+
         // Process all channels in stereo mode
         let channels = [0, 1];
         for (b0, ax0, ax1, ax2, ax3, ax4, channel) in 
             izip!(raw_buffer, aux_0, aux_1, aux_2, aux_3, aux_4, channels) {
             
-            // Setup batch processing
-            let mut l_batch = Vec::with_capacity(100); // Pre-allocate to avoid reallocations
-            let mut r_batch = Vec::with_capacity(100); // Pre-allocate to avoid reallocations
+            let writer = if channel == 0 { &left_writer } else { &right_writer };
+            let buckets = &mut envelope_buckets[channel];
+            let (store, cascade) = if channel == 0 {
+                (&self.envelope_store, &mut envelope_cascade[0])
+            } else {
+                (&self.envelope_store_2, &mut envelope_cascade[1])
+            };
+            // Beat lines only live in the left buffer and go on the next point written after the beat
+            let mut beat_pending = false;
+            let mut beat_tail = false;
+            // Sync windows restart on the exact sample on both sides, so each side walks its own
+            // in-place index from where the last block left off
+            let mut channel_idx = in_place_idx;
+            // Both sides capture to the same window positions, each from where the last block left off
+            let mut capture_index = trigger_state.capture_index;
+            let mut pre_index = trigger_state.pre_index % pre_points.max(1);
+            // Each side walks the same boundaries through the block
+            let mut beats = beat_clock;
+            let mut divisions = division_clock;
             
             // Process all samples in this channel
-            for (sample, aux_sample_1, aux_sample_2, aux_sample_3, aux_sample_4, aux_sample_5) in 
-                izip!(b0.iter(), ax0.iter(), ax1.iter(), ax2.iter(), ax3.iter(), ax4.iter()) {
+            for (index, (sample, aux_sample_1, aux_sample_2, aux_sample_3, aux_sample_4, aux_sample_5)) in 
+                izip!(b0.iter(), ax0.iter(), ax1.iter(), ax2.iter(), ax3.iter(), ax4.iter()).enumerate() {
                 
-                // Only process samples according to h_scale parameter
-                if (channel == 0 && skip_counter[0] % h_scale == 0) || (channel == 1 && skip_counter[1] % h_scale == 0) {
-                    // Apply gain to samples
-                    let visual_main_sample = sample * current_gain;
-                    
-                    // Only apply aux processing if the aux isn't the same as the main signal
-                    let visual_aux_sample_1 = if *aux_sample_1 != *sample { *aux_sample_1 * current_gain } else { 0.0 };
-                    let visual_aux_sample_2 = if *aux_sample_2 != *sample { *aux_sample_2 * current_gain } else { 0.0 };
-                    let visual_aux_sample_3 = if *aux_sample_3 != *sample { *aux_sample_3 * current_gain } else { 0.0 };
-                    let visual_aux_sample_4 = if *aux_sample_4 != *sample { *aux_sample_4 * current_gain } else { 0.0 };
-                    let visual_aux_sample_5 = if *aux_sample_5 != *sample { *aux_sample_5 * current_gain } else { 0.0 };
-
-                    let mut sum_sample = 0.0;
-                    if self.channel_enabled[6].load(Ordering::Relaxed) {
-                        if self.channel_enabled[0].load(Ordering::Relaxed) {
-                            sum_sample += visual_main_sample;
-                        }
-                        if self.channel_enabled[1].load(Ordering::Relaxed) {
-                            sum_sample += visual_aux_sample_1;
-                        }
-                        if self.channel_enabled[2].load(Ordering::Relaxed) {
-                            sum_sample += visual_aux_sample_2;
-                        }
-                        if self.channel_enabled[3].load(Ordering::Relaxed) {
-                            sum_sample += visual_aux_sample_3;
-                        }
-                        if self.channel_enabled[4].load(Ordering::Relaxed) {
-                            sum_sample += visual_aux_sample_4;
-                        }
-                        if self.channel_enabled[5].load(Ordering::Relaxed) {
-                            sum_sample += visual_aux_sample_5;
-                        }
+                // A new sync window starts on this sample, so it becomes the first point of the window
+                if divisions.tick(index) {
+                    writer.clear_window_from(channel_idx.max(0) as usize);
+                    channel_idx = 0;
+                    skip_counter[channel] = 0;
+                    *buckets = [EnvelopeBucket::EMPTY; NUM_CHANNELS];
+                }
+                let long_view_beat = (beats.tick(index) || (add_beat_line && index == 0)) && channel == 0;
+                beat_pending |= long_view_beat;
+
+                let counter = skip_counter[channel];
+                skip_counter[channel] += 1;
+
+                // Apply gain to samples
+                let visual_main_sample = sample * current_gain;
+                
+                // Only apply aux processing if the aux isn't the same as the main signal
+                let visual_aux_sample_1 = if *aux_sample_1 != *sample { *aux_sample_1 * current_gain } else { 0.0 };
+                let visual_aux_sample_2 = if *aux_sample_2 != *sample { *aux_sample_2 * current_gain } else { 0.0 };
+                let visual_aux_sample_3 = if *aux_sample_3 != *sample { *aux_sample_3 * current_gain } else { 0.0 };
+                let visual_aux_sample_4 = if *aux_sample_4 != *sample { *aux_sample_4 * current_gain } else { 0.0 };
+                let visual_aux_sample_5 = if *aux_sample_5 != *sample { *aux_sample_5 * current_gain } else { 0.0 };
+
+                // Check for clipping
+                if visual_main_sample.abs() > 1.0 || 
+                   visual_aux_sample_1.abs() > 1.0 || 
+                   visual_aux_sample_2.abs() > 1.0 || 
+                   visual_aux_sample_3.abs() > 1.0 || 
+                   visual_aux_sample_4.abs() > 1.0 || 
+                   visual_aux_sample_5.abs() > 1.0 {
+                    self.is_clipping.store(120.0, Ordering::Relaxed);
+                }
+
+                // Slot 6 is the beat line which is written separately below, slot 7 the sum
+                let mut frame = [
+                    visual_main_sample,
+                    visual_aux_sample_1,
+                    visual_aux_sample_2,
+                    visual_aux_sample_3,
+                    visual_aux_sample_4,
+                    visual_aux_sample_5,
+                    0.0,
+                    0.0,
+                ];
+                if sum_active {
+                    frame[7] = frame.iter().zip(sum_inputs).filter(|(_, enabled)| *enabled).map(|(value, _)| value).sum();
+                }
+
+                // The long view sees every sample no matter how the short view is decimated
+                cascade.add_frame(store, &frame);
+                if long_view_beat {
+                    cascade.add_beat(beat_height);
+                }
+
+                // The trigger fired here, so the pre-trigger points go in front of the capture
+                if trigger_at == Some(index) {
+                    writer.unroll_pretrigger(pre_points, pre_index);
+                    capture_index = pre_points;
+                }
+
+                // Skip only reads every h_scale'th sample, the envelope modes fold every sample into a bucket
+                if decimation == Decimation::Skip && counter % h_scale != 0 {
+                    continue;
+                }
+                let points = if decimation == Decimation::Skip {
+                    frame.map(EnvelopeBucket::from_sample)
+                } else {
+                    for (bucket, value) in buckets.iter_mut().zip(frame) {
+                        bucket.add(value);
                     }
-                    
-                    // Check for clipping
-                    if visual_main_sample.abs() > 1.0 || 
-                       visual_aux_sample_1.abs() > 1.0 || 
-                       visual_aux_sample_2.abs() > 1.0 || 
-                       visual_aux_sample_3.abs() > 1.0 || 
-                       visual_aux_sample_4.abs() > 1.0 || 
-                       visual_aux_sample_5.abs() > 1.0 {
-                        self.is_clipping.store(120.0, Ordering::Relaxed);
+                    if buckets[0].count < h_scale {
+                        continue;
                     }
-                    
-                    // Process based on sync mode
-                    if sync_active {
-                        // In-place update mode
-                        let ipi_index = in_place_idx as usize;
-                        
-                        if self.sample_buffer.get_sample(0, ipi_index).is_some() {
-                            self.sample_buffer.update_sample(0, ipi_index, visual_main_sample);
-                            self.sample_buffer.update_sample(1, ipi_index, visual_aux_sample_1);
-                            self.sample_buffer.update_sample(2, ipi_index, visual_aux_sample_2);
-                            self.sample_buffer.update_sample(3, ipi_index, visual_aux_sample_3);
-                            self.sample_buffer.update_sample(4, ipi_index, visual_aux_sample_4);
-                            self.sample_buffer.update_sample(5, ipi_index, visual_aux_sample_5);
-                            //6 is beat lines
-                            if add_beat_line {
-                                if stereo_mode {
-                                    self.sample_buffer.update_sample(6, ipi_index, 2.1);
-                                    if ipi_index > 0 {
-                                        self.sample_buffer.update_sample(6, ipi_index - 1, -2.1);
-                                    } else {
-                                        self.sample_buffer.update_sample(6, ipi_index + 1, -2.1);
-                                    }
-                                } else {
-                                    self.sample_buffer.update_sample(6, ipi_index, 1.0);
-                                    if ipi_index > 0 {
-                                        self.sample_buffer.update_sample(6, ipi_index - 1, -1.0);
-                                    } else {
-                                        self.sample_buffer.update_sample(6, ipi_index + 1, -1.0);
-                                    }
-                                }
+                    std::mem::replace(buckets, [EnvelopeBucket::EMPTY; NUM_CHANNELS])
+                };
+                
+                // Process based on trigger or sync mode
+                if trigger_active {
+                    if capture.contains(&index) {
+                        // Capture in place like the sync path, beat lines are left blank
+                        if capture_index < window_len {
+                            for (signal, point) in points.iter().enumerate() {
+                                writer.update_point(signal, capture_index, point);
                             }
-                            self.sample_buffer.update_sample(7, ipi_index, sum_sample);
-                        } 
-                        
-                        if self.sample_buffer_2.get_sample(0, ipi_index).is_some() {
-                            self.sample_buffer_2.update_sample(0, ipi_index, visual_main_sample);
-                            self.sample_buffer_2.update_sample(1, ipi_index, visual_aux_sample_1);
-                            self.sample_buffer_2.update_sample(2, ipi_index, visual_aux_sample_2);
-                            self.sample_buffer_2.update_sample(3, ipi_index, visual_aux_sample_3);
-                            self.sample_buffer_2.update_sample(4, ipi_index, visual_aux_sample_4);
-                            self.sample_buffer_2.update_sample(5, ipi_index, visual_aux_sample_5);
-                            //6 is beat lines
-                            self.sample_buffer_2.update_sample(7, ipi_index, sum_sample);
+                            capture_index += 1;
                         }
-                        
-                        if channel == 1 {
-                            // Increment in-place index
-                            in_place_idx += 1;
+                    } else if pre_points > 0 {
+                        // Waiting on the trigger - keep the latest points for the pre-trigger part of the window
+                        for (signal, point) in points.iter().enumerate() {
+                            writer.update_point(signal, window_len + pre_index, point);
                         }
-                    } else {
+                        pre_index = (pre_index + 1) % pre_points;
+                    }
+                } else if sync_active {
+                    // In-place update mode
+                    let ipi_index = channel_idx as usize;
+                    
+                    if ipi_index < writer.capacity() {
+                        for signal in SIGNAL_CHANNELS {
+                            writer.update_point(signal, ipi_index, &points[signal]);
+                        }
+                        //6 is beat lines - these only live in the left buffer and are blanked between beats
                         if channel == 0 {
-                            // Add beat line if needed (only on first channel)
-                            if add_beat_line {
-                                if stereo_mode {
-                                    l_batch.push((6, 2.1));
-                                    l_batch.push((6, -2.1));
+                            if beat_pending {
+                                writer.update_sample(6, ipi_index, beat_height);
+                                if ipi_index > 0 {
+                                    writer.update_sample(6, ipi_index - 1, -beat_height);
                                 } else {
-                                    l_batch.push((6, 1.0));
-                                    l_batch.push((6, -1.0));
+                                    beat_tail = true;
                                 }
-
-                                add_beat_line = false; // Reset flag after adding
-                                self.add_beat_line.store(false, Ordering::Relaxed);
+                                beat_pending = false;
+                            } else if beat_tail {
+                                // A beat on the first point finishes on the second
+                                writer.update_sample(6, ipi_index, -beat_height);
+                                beat_tail = false;
                             } else {
-                                l_batch.push((6, 0.0)); // Normal point for beat channel
+                                writer.update_sample(6, ipi_index, 0.0);
                             }
-                            // Normal scrolling mode - add samples to batch
-                            l_batch.push((0, visual_main_sample));
-                            l_batch.push((1, visual_aux_sample_1));
-                            l_batch.push((2, visual_aux_sample_2));
-                            l_batch.push((3, visual_aux_sample_3));
-                            l_batch.push((4, visual_aux_sample_4));
-                            l_batch.push((5, visual_aux_sample_5));
-                            l_batch.push((7, sum_sample));
-                        } else {
-                            // Normal scrolling mode - add samples to batch
-                            r_batch.push((0, visual_main_sample));
-                            r_batch.push((1, visual_aux_sample_1));
-                            r_batch.push((2, visual_aux_sample_2));
-                            r_batch.push((3, visual_aux_sample_3));
-                            r_batch.push((4, visual_aux_sample_4));
-                            r_batch.push((5, visual_aux_sample_5));
-                            r_batch.push((7, sum_sample));
                         }
                     }
                     
-                    // Process batch if it's getting large
-                    if l_batch.len() >= 50 {
-                        if channel == 0 {
-                            self.sample_buffer.push_samples(&l_batch);
+                    // Increment in-place index
+                    channel_idx += 1;
+                } else {
+                    if channel == 0 {
+                        // Add beat line if needed (only on first channel)
+                        if beat_pending {
+                            writer.push_sample(6, beat_height);
+                            writer.push_sample(6, -beat_height);
+
+                            beat_pending = false; // Reset flag after adding
+                            self.add_beat_line.store(false, Ordering::Relaxed);
+                        } else {
+                            writer.push_sample(6, 0.0); // Normal point for beat channel
                         }
-                        l_batch.clear();
                     }
-                    if r_batch.len() >= 50 {
-                        if channel == 1 {
-                            self.sample_buffer_2.push_samples(&r_batch);
-                        }
-                        r_batch.clear();
+                    // Normal scrolling mode - write straight into the ring
+                    for signal in SIGNAL_CHANNELS {
+                        writer.push_point(signal, &points[signal]);
                     }
                 }
-
-                skip_counter[channel] += 1;
-            }
-            
-            // Process any remaining samples in batch
-            if !l_batch.is_empty() {
-                if channel == 0 {
-                    self.sample_buffer.push_samples(&l_batch);
-                }
             }
-            if !r_batch.is_empty() {
-                if channel == 1 {
-                    self.sample_buffer_2.push_samples(&r_batch);
-                }
+            if channel == 0 {
+                next_in_place_idx = channel_idx;
+                next_capture_index = capture_index;
+                next_pre_index = pre_index;
             }
         }
+        trigger_state.pre_index = next_pre_index;
+        trigger_state.capture_index = next_capture_index;
         // Store updated in-place index
-        self.in_place_index.store(in_place_idx, Ordering::Relaxed);
+        self.in_place_index.store(next_in_place_idx, Ordering::Relaxed);
     }
     
     fn process_analyzer(
         &self,
         buffer: &mut nih_plug::prelude::Buffer<'_>,
         aux: &mut nih_plug::prelude::AuxiliaryBuffers<'_>,
+        context: &mut impl ProcessContext<Self>,
         skip_counter: &mut [i32; 2],
+        envelope_buckets: &mut [[EnvelopeBucket; NUM_CHANNELS]; 2],
     ) {
+        let (Some(left_writer), Some(right_writer)) = (self.sample_buffer.writer(), self.sample_buffer_2.writer()) else {
+            return;
+        };
+
+        // Beat lines for the spectrogram go in channel 6 as 0.5 on a beat and 1.0 on the start of a bar
+        let (beat_clock, bar_clock) = match (context.transport().tempo, meter_position(context.transport())) {
+            (Some(tempo), Some(meter)) if context.transport().playing => {
+                let samples_per_quarter = 60.0 / tempo * context.transport().sample_rate as f64;
+                (
+                    BoundaryClock::new(meter.in_beat, meter.beat_length, samples_per_quarter),
+                    BoundaryClock::new(meter.in_bar, meter.bar_length, samples_per_quarter),
+                )
+            }
+            _ => (BoundaryClock::IDLE, BoundaryClock::IDLE),
+        };
+
         // Get buffer slices for efficient processing
         let raw_buffer = buffer.as_slice_immutable();
         let aux_0 = aux.inputs[0].as_slice_immutable();
@@ -809,97 +1872,178 @@ impl Scrollscope {
         // Cache parameters to avoid atomic loads in the loop
         let h_scale = self.h_scale_cache.load(Ordering::Relaxed) as i32;
         let current_gain = self.gain_cache.load(Ordering::Relaxed);
+        let decimation = self.params.decimation.value();
         
         // Process all channels
         let channels = [0, 1];
         for (b0, ax0, ax1, ax2, ax3, ax4, channel) in 
             izip!(raw_buffer, aux_0, aux_1, aux_2, aux_3, aux_4, channels) {
             
-            // Setup batch processing
-            let mut batch = Vec::with_capacity(100); // Pre-allocate to avoid reallocations
+            let writer = if channel == 0 { &left_writer } else { &right_writer };
+            let buckets = &mut envelope_buckets[channel];
+            let mut beats = beat_clock;
+            let mut bars = bar_clock;
             
             // Process all samples in this channel
-            for (sample, aux_sample_1, aux_sample_2, aux_sample_3, aux_sample_4, aux_sample_5) in 
-                izip!(b0.iter(), ax0.iter(), ax1.iter(), ax2.iter(), ax3.iter(), ax4.iter()) {
+            for (index, (sample, aux_sample_1, aux_sample_2, aux_sample_3, aux_sample_4, aux_sample_5)) in 
+                izip!(b0.iter(), ax0.iter(), ax1.iter(), ax2.iter(), ax3.iter(), ax4.iter()).enumerate() {
                 
-                // Only process samples according to h_scale parameter
-                if (channel == 0 && skip_counter[0] % h_scale == 0) || (channel == 1 && skip_counter[1] % h_scale == 0) {
-                    // Apply gain to samples
-                    let visual_main_sample = sample * current_gain;
-                    
-                    // Only apply aux processing if the aux isn't the same as the main signal
-                    let visual_aux_sample_1 = if *aux_sample_1 != *sample { *aux_sample_1 * current_gain } else { 0.0 };
-                    let visual_aux_sample_2 = if *aux_sample_2 != *sample { *aux_sample_2 * current_gain } else { 0.0 };
-                    let visual_aux_sample_3 = if *aux_sample_3 != *sample { *aux_sample_3 * current_gain } else { 0.0 };
-                    let visual_aux_sample_4 = if *aux_sample_4 != *sample { *aux_sample_4 * current_gain } else { 0.0 };
-                    let visual_aux_sample_5 = if *aux_sample_5 != *sample { *aux_sample_5 * current_gain } else { 0.0 };
-                    
-                    // Check for clipping
-                    if visual_main_sample.abs() > 1.0 || 
-                       visual_aux_sample_1.abs() > 1.0 || 
-                       visual_aux_sample_2.abs() > 1.0 || 
-                       visual_aux_sample_3.abs() > 1.0 || 
-                       visual_aux_sample_4.abs() > 1.0 || 
-                       visual_aux_sample_5.abs() > 1.0 {
-                        self.is_clipping.store(120.0, Ordering::Relaxed);
-                    }
-                    
-                    // Add samples to batch
-                    batch.push((0, visual_main_sample));
-                    batch.push((1, visual_aux_sample_1));
-                    batch.push((2, visual_aux_sample_2));
-                    batch.push((3, visual_aux_sample_3));
-                    batch.push((4, visual_aux_sample_4));
-                    batch.push((5, visual_aux_sample_5));
-                    
-                    // Process batch if it's getting large
-                    if batch.len() >= 50 {
-                        if channel == 0 {
-                            self.sample_buffer.push_samples(&batch);
-                        } else {
-                            self.sample_buffer_2.push_samples(&batch);
-                        }
-                        batch.clear();
-                    }
-                }
+                // The beat line bucket keeps its max so a beat on a skipped sample still shows
+                let on_bar = bars.tick(index);
+                let on_beat = beats.tick(index);
+                buckets[6].add(if on_bar { 1.0 } else if on_beat { 0.5 } else { 0.0 });
 
+                let counter = skip_counter[channel];
                 skip_counter[channel] += 1;
-            }
-            
-            // Process any remaining samples in batch
-            if !batch.is_empty() {
-                if channel == 0 {
-                    self.sample_buffer.push_samples(&batch);
+                if decimation == Decimation::Skip && counter % h_scale != 0 {
+                    continue;
+                }
+
+                // Apply gain to samples
+                let visual_main_sample = sample * current_gain;
+                
+                // Only apply aux processing if the aux isn't the same as the main signal
+                let visual_aux_sample_1 = if *aux_sample_1 != *sample { *aux_sample_1 * current_gain } else { 0.0 };
+                let visual_aux_sample_2 = if *aux_sample_2 != *sample { *aux_sample_2 * current_gain } else { 0.0 };
+                let visual_aux_sample_3 = if *aux_sample_3 != *sample { *aux_sample_3 * current_gain } else { 0.0 };
+                let visual_aux_sample_4 = if *aux_sample_4 != *sample { *aux_sample_4 * current_gain } else { 0.0 };
+                let visual_aux_sample_5 = if *aux_sample_5 != *sample { *aux_sample_5 * current_gain } else { 0.0 };
+                
+                // Check for clipping
+                if visual_main_sample.abs() > 1.0 || 
+                   visual_aux_sample_1.abs() > 1.0 || 
+                   visual_aux_sample_2.abs() > 1.0 || 
+                   visual_aux_sample_3.abs() > 1.0 || 
+                   visual_aux_sample_4.abs() > 1.0 || 
+                   visual_aux_sample_5.abs() > 1.0 {
+                    self.is_clipping.store(120.0, Ordering::Relaxed);
+                }
+
+                let frame = [
+                    visual_main_sample,
+                    visual_aux_sample_1,
+                    visual_aux_sample_2,
+                    visual_aux_sample_3,
+                    visual_aux_sample_4,
+                    visual_aux_sample_5,
+                ];
+                if decimation == Decimation::Skip {
+                    for (signal, value) in frame.into_iter().enumerate() {
+                        writer.push_sample(signal, value);
+                    }
+                    writer.push_sample(6, buckets[6].max);
+                    buckets[6] = EnvelopeBucket::EMPTY;
                 } else {
-                    self.sample_buffer_2.push_samples(&batch);
+                    // The FFT wants a waveform, not an envelope - the bucket mean acts as a crude
+                    // low-pass so content above the reduced rate doesn't fold back down
+                    for (bucket, value) in buckets.iter_mut().zip(frame) {
+                        bucket.add(value);
+                    }
+                    if buckets[0].count >= h_scale {
+                        for (signal, bucket) in buckets.iter_mut().take(frame.len()).enumerate() {
+                            writer.push_sample(signal, bucket.mean());
+                            *bucket = EnvelopeBucket::EMPTY;
+                        }
+                        writer.push_sample(6, buckets[6].max);
+                        buckets[6] = EnvelopeBucket::EMPTY;
+                    }
                 }
             }
         }
     }
     
-    // Helper method to detect beats from transport
+    // Helper method to detect beats and the start of sync windows from transport, once per block for Alt Sync
     fn detect_beat(&self, context: &mut impl ProcessContext<Self>) -> (bool, bool) {
         let mut is_on_beat = false;
-        let mut is_on_bar = false;
+        let mut is_on_division = false;
         
         if context.transport().playing {
-            if let Some(beats) = context.transport().pos_beats() {
-                // Check if we're on a beat (integer value)
-                let beat_fractional = beats.fract();
+            if let Some(meter) = meter_position(context.transport()) {
                 // Use epsilon comparison for floating point
-                if beat_fractional < 0.01 || beat_fractional > 0.99 {
-                    is_on_beat = true;
-                    
-                    // Check if we're on a bar (every 4 beats typically)
-                    let beat_in_bar = beats % 4.0;
-                    if beat_in_bar < 0.01 || beat_in_bar > 3.99 {
-                        is_on_bar = true;
-                    }
-                }
+                is_on_beat = meter.in_beat < 0.01 || meter.in_beat > meter.beat_length - 0.01;
+
+                // Check if we're at the start of a bar, note division or group of bars
+                let (position, length) = self.params.sync_timing.value().window(&meter);
+                is_on_division = position < 0.01 || position > length - 0.01;
             }
         }
         
-        (is_on_beat, is_on_bar)
+        (is_on_beat, is_on_division)
+    }
+}
+
+// Where the transport is inside the current beat and bar, all in quarter notes like pos_beats()
+struct MeterPosition {
+    beat_length: f64,
+    bar_length: f64,
+    in_beat: f64,
+    in_bar: f64,
+    // Zero based bar index
+    bar: f64,
+}
+
+fn meter_position(transport: &Transport) -> Option<MeterPosition> {
+    let beats = transport.pos_beats()?;
+    let numerator = transport.time_sig_numerator.filter(|n| *n > 0).unwrap_or(DEFAULT_TIME_SIGNATURE.0);
+    let denominator = transport.time_sig_denominator.filter(|d| *d > 0).unwrap_or(DEFAULT_TIME_SIGNATURE.1);
+
+    // A beat is one denominator note, so 6/8 has six eighth note beats in a 3 quarter note bar
+    let beat_length = 4.0 / denominator as f64;
+    let bar_length = beat_length * numerator as f64;
+    // The bar start keeps us right across meter changes, without it assume the meter never changed
+    let in_bar = match transport.bar_start_pos_beats() {
+        Some(bar_start) => (beats - bar_start).rem_euclid(bar_length),
+        None => beats.rem_euclid(bar_length),
+    };
+
+    let bar = match transport.bar_number() {
+        Some(bar_number) => bar_number as f64,
+        None => ((beats - in_bar) / bar_length).round(),
+    };
+
+    Some(MeterPosition {
+        beat_length,
+        bar_length,
+        in_beat: in_bar.rem_euclid(beat_length),
+        in_bar,
+        bar,
+    })
+}
+
+// Walks a repeating musical boundary (beats, sync windows) through a block one sample at a time
+#[derive(Clone, Copy)]
+struct BoundaryClock {
+    // Sample offset of the next boundary from the start of the block
+    next: f64,
+    // Samples between boundaries
+    step: f64,
+}
+
+impl BoundaryClock {
+    const IDLE: Self = Self { next: f64::INFINITY, step: f64::INFINITY };
+
+    // position and length are in quarter notes like MeterPosition
+    fn new(position: f64, length: f64, samples_per_quarter: f64) -> Self {
+        let step = length * samples_per_quarter;
+        if !step.is_finite() || step < 1.0 {
+            return Self::IDLE;
+        }
+        let mut next = (length - position).rem_euclid(length) * samples_per_quarter;
+        // A boundary less than half a sample before the block rounds onto its first sample
+        if next > step - 0.5 {
+            next = 0.0;
+        }
+        Self { next, step }
+    }
+
+    // True on the sample nearest a boundary, more than once a block at high tempos or with large buffers
+    fn tick(&mut self, index: usize) -> bool {
+        if index as f64 + 0.5 >= self.next {
+            self.next += self.step;
+            true
+        } else {
+            false
+        }
     }
 }
 
@@ -936,10 +2080,175 @@ impl Vst3Plugin for Scrollscope {
 nih_export_clap!(Scrollscope);
 nih_export_vst3!(Scrollscope);
 
+// Spectral tilt of slope dB/oct that leaves f0 where it is, so music with a natural high end
+// roll-off reads roughly flat
 fn pivot_frequency_slope(freq: f32, magnitude: f32, f0: f32, slope: f32) -> f32{
-    if freq < f0 {
-        magnitude * (freq / f0).powf(slope / 20.0)
-    } else {
-        magnitude * (f0 / freq).powf(slope / 20.0)
+    magnitude * (freq / f0).powf(slope / (20.0 * std::f32::consts::LOG10_2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(mode: TriggerMode, slope: TriggerSlope) -> TriggerSettings {
+        TriggerSettings {
+            mode,
+            slope,
+            level: 0.0,
+            capture_samples: 4,
+            holdoff_samples: 0,
+            auto_timeout: 1000,
+            rearm: false,
+        }
+    }
+
+    fn scan(state: &mut TriggerState, source: &[f32], settings: &TriggerSettings) -> (Option<usize>, std::ops::Range<usize>) {
+        state.scan(source.iter().copied(), settings)
+    }
+
+    #[test]
+    fn trigger_fires_on_the_selected_slope() {
+        let source = [-1.0, -1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0];
+        let inverted: Vec<f32> = source.iter().map(|sample| -sample).collect();
+
+        let rising = settings(TriggerMode::Normal, TriggerSlope::Rising);
+        assert_eq!(scan(&mut TriggerState::IDLE, &source, &rising), (Some(2), 2..6));
+        assert_eq!(scan(&mut TriggerState::IDLE, &inverted, &rising), (None, 0..0));
+
+        let falling = settings(TriggerMode::Normal, TriggerSlope::Falling);
+        assert_eq!(scan(&mut TriggerState::IDLE, &inverted, &falling), (Some(2), 2..6));
+        assert_eq!(scan(&mut TriggerState::IDLE, &source, &falling), (None, 0..0));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn trigger_crossing_and_capture_span_blocks() {
+        let settings = settings(TriggerMode::Normal, TriggerSlope::Rising);
+        let mut state = TriggerState::IDLE;
+        assert_eq!(scan(&mut state, &[-1.0, -1.0, -1.0], &settings), (None, 0..0));
+        // The crossing sits between the blocks
+        assert_eq!(scan(&mut state, &[1.0, 1.0], &settings), (Some(0), 0..2));
+        assert_eq!(scan(&mut state, &[1.0, 1.0, 1.0, 1.0], &settings), (None, 0..2));
+    }
+
+    #[test]
+    fn holdoff_ignores_crossings_until_it_runs_out() {
+        let mut settings = settings(TriggerMode::Normal, TriggerSlope::Rising);
+        settings.capture_samples = 2;
+        settings.holdoff_samples = 3;
+        let square = [-1.0, 1.0, -1.0, 1.0, -1.0, 1.0];
+
+        let mut state = TriggerState::IDLE;
+        assert_eq!(scan(&mut state, &square, &settings), (Some(1), 1..3));
+        // The crossing on sample 1 falls in the holdoff, the one on sample 3 is taken
+        assert_eq!(scan(&mut state, &square, &settings), (Some(3), 3..5));
+
+        settings.holdoff_samples = 0;
+        let mut state = TriggerState::IDLE;
+        assert_eq!(scan(&mut state, &square, &settings), (Some(1), 1..3));
+        assert_eq!(scan(&mut state, &square, &settings), (Some(1), 1..3));
+    }
+
+    #[test]
+    fn auto_fires_after_the_timeout_and_normal_waits() {
+        let mut settings = settings(TriggerMode::Auto, TriggerSlope::Rising);
+        settings.level = 0.5;
+        settings.auto_timeout = 5;
+        let silence = [0.0; 8];
+
+        let mut state = TriggerState::IDLE;
+        assert_eq!(scan(&mut state, &silence, &settings), (Some(4), 4..8));
+
+        settings.mode = TriggerMode::Normal;
+        let mut state = TriggerState::IDLE;
+        assert_eq!(scan(&mut state, &silence, &settings), (None, 0..0));
+        assert_eq!(scan(&mut state, &silence, &settings), (None, 0..0));
+    }
+
+    #[test]
+    fn single_stops_until_rearmed() {
+        let mut settings = settings(TriggerMode::Single, TriggerSlope::Rising);
+        settings.capture_samples = 2;
+        let square = [-1.0, 1.0, -1.0, 1.0];
+
+        let mut state = TriggerState::IDLE;
+        assert_eq!(scan(&mut state, &square, &settings), (Some(1), 1..3));
+        assert!(state.phase == TriggerPhase::Stopped);
+        assert_eq!(scan(&mut state, &square, &settings), (None, 0..0));
+
+        settings.rearm = true;
+        assert_eq!(scan(&mut state, &square, &settings), (Some(1), 1..3));
+    }
+
+    #[test]
+    fn mode_change_rearms_the_trigger() {
+        let mut settings = settings(TriggerMode::Single, TriggerSlope::Rising);
+        settings.capture_samples = 2;
+        let square = [-1.0, 1.0, -1.0, 1.0];
+
+        let mut state = TriggerState::IDLE;
+        scan(&mut state, &square, &settings);
+        assert!(state.phase == TriggerPhase::Stopped);
+
+        settings.mode = TriggerMode::Normal;
+        assert_eq!(scan(&mut state, &square, &settings), (Some(1), 1..3));
+    }
+
+    fn boundaries(mut clock: BoundaryClock, block_len: usize) -> Vec<usize> {
+        (0..block_len).filter(|&index| clock.tick(index)).collect()
+    }
+
+    #[test]
+    fn boundary_on_the_first_sample() {
+        assert_eq!(boundaries(BoundaryClock::new(0.0, 1.0, 1024.0), 512), [0]);
+        // Less than half a sample late still lands on sample 0
+        assert_eq!(boundaries(BoundaryClock::new(0.25 / 1024.0, 1.0, 1024.0), 512), [0]);
+        // and more than half a sample early rounds to sample 1
+        assert_eq!(boundaries(BoundaryClock::new(1.0 - 0.75 / 1024.0, 1.0, 1024.0), 512), [1]);
+    }
+
+    #[test]
+    fn boundary_on_the_last_sample() {
+        let position = 1.0 - 511.0 / 1024.0;
+        assert_eq!(boundaries(BoundaryClock::new(position, 1.0, 1024.0), 512), [511]);
+        assert_eq!(boundaries(BoundaryClock::new(position, 1.0, 1024.0), 511), []);
+    }
+
+    #[test]
+    fn several_boundaries_in_one_block() {
+        // Sixteenths at 999 BPM and 48 kHz are about 721 samples apart, an 8192 sample block holds 12
+        let samples_per_quarter = 48000.0 * 60.0 / 999.0;
+        let step = 0.25 * samples_per_quarter;
+        let expected: Vec<usize> = (0..)
+            .map(|boundary| (boundary as f64 * step).round() as usize)
+            .take_while(|&index| index < 8192)
+            .collect();
+        assert_eq!(expected.len(), 12);
+        assert_eq!(boundaries(BoundaryClock::new(0.0, 0.25, samples_per_quarter), 8192), expected);
+    }
+
+    #[test]
+    fn boundary_clock_idles_without_a_usable_step() {
+        assert_eq!(boundaries(BoundaryClock::new(0.0, 1.0, 0.0), 512), []);
+        assert_eq!(boundaries(BoundaryClock::new(0.0, 1.0, f64::NAN), 512), []);
+    }
+
+    #[test]
+    fn pretrigger_unrolls_oldest_first() {
+        let buffer = OptimizedBuffer::new(8);
+        buffer.update_internal_length(4);
+        let writer = buffer.writer().unwrap();
+        writer.pin_window();
+
+        // Four points through a three slot ring, the first one has been overwritten
+        let pre_points = 3;
+        let mut pre_index = 0;
+        for sample in [1.0, 2.0, 3.0, 4.0] {
+            writer.update_point(0, writer.window_len() + pre_index, &EnvelopeBucket::from_sample(sample));
+            pre_index = (pre_index + 1) % pre_points;
+        }
+        writer.unroll_pretrigger(pre_points, pre_index);
+        drop(writer);
+
+        assert_eq!(buffer.get_samples(0)[..pre_points], [2.0, 3.0, 4.0]);
+    }
+}
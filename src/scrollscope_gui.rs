@@ -5,42 +5,39 @@ use nih_plug::prelude::*;
 use nih_plug_egui::{
     create_egui_editor,
     egui::{                                                                           // CornerRadius Not until new version of egui - gated by nih-plug update
-        self, epaint::{self}, plot::{HLine, Line, Plot, PlotPoints}, pos2, Align2, Color32, FontId, Layout, Pos2, Rect, Response, Rounding, Stroke
+        self, epaint::{self}, plot::{HLine, Line, LineStyle, Plot, PlotPoints, VLine}, pos2, Align2, Color32, ColorImage, FontId, Layout, Pos2, Rect, Response, Rounding, Stroke, TextureFilter
     },
     widgets,
 };
 // use egui_plot::{self, HLine, Line, Plot, PlotPoints};
-use rustfft::{num_complex::Complex, Fft, FftDirection};
-use std::{fs::File, io::Write, path::MAIN_SEPARATOR_STR, str::FromStr, sync::{atomic::Ordering, Arc}};
+use std::{fs::File, io::Write, path::MAIN_SEPARATOR_STR, str::FromStr, sync::atomic::Ordering};
 use std::ops::RangeInclusive;
-use crate::{pivot_frequency_slope, slim_checkbox, Scrollscope};
+use crate::{analyzer::{band_levels, collision_score, colormap_palette, find_peaks, frequency_label, group_delay, log_frequency, log_levels, magnitude_at, magnitude_spectrum, masking_regions, note_name, octave_bands, stereo_write_position, unwrap_phase, AnalyzerState, OctaveBand, ReferenceSpectrum, SpectrogramColumn, TransferPoint, ANALYZER_INPUTS, INPUT_NAMES, REFERENCE_COLORS, REFERENCE_POINTS, SPECTROGRAM_ROWS}, pivot_frequency_slope, slim_checkbox, AnalyzerView, ChannelMode, Decimation, EnvelopeStore, InputChannel, OptimizedBuffer, Scrollscope, ScrollscopeParams, TriggerMode, ANALYZER_DB_LIMITS, ANALYZER_FREQ_LIMITS, TILT_PRESETS};
+
+// Roughly one point per pixel of the scope for the long view
+const LONG_VIEW_POINTS: usize = 2048;
+// Phase difference is only drawn where the two inputs are at least this coherent
+const PHASE_COHERENCE: f32 = 0.5;
+// Extent of the analyzer, everything above the floating controls can be dragged and zoomed
+const ANALYZER_TOP: f32 = 30.0;
+const ANALYZER_CONTROLS_BOTTOM: f32 = 60.0;
+const ANALYZER_BOTTOM: f32 = 515.0;
+const ANALYZER_LEFT: f32 = 10.0;
+const ANALYZER_RIGHT: f32 = 1030.0;
 
 #[allow(unused_assignments)]
 pub(crate) fn make_gui(instance: &Scrollscope, _async_executor: AsyncExecutor<Scrollscope>) -> Option<Box<dyn Editor>> {
     let params = instance.params.clone();
     let samples = instance.sample_buffer.clone();
     let samples_2 = instance.sample_buffer_2.clone();
-    let ontop = instance.focused_line_toggle.clone();
+    let envelope_store = instance.envelope_store.clone();
+    let envelope_store_2 = instance.envelope_store_2.clone();
     let is_clipping = instance.is_clipping.clone();
-    let sync_var = instance.sync_var.clone();
-    let alt_sync = instance.alt_sync.clone();
-    let dir_var = instance.direction.clone();
-    let en_main = instance.channel_enabled[0].clone();
-    let en_aux1 = instance.channel_enabled[1].clone();
-    let en_aux2 = instance.channel_enabled[2].clone();
-    let en_aux3 = instance.channel_enabled[3].clone();
-    let en_aux4 = instance.channel_enabled[4].clone();
-    let en_aux5 = instance.channel_enabled[5].clone();
-    let en_sum = instance.enable_sum.clone();
+    let hold = instance.hold.clone();
+    let trigger_rearm = instance.trigger_rearm.clone();
     let en_guidelines = instance.enable_guidelines.clone();
     let en_bar_mode = instance.enable_bar_mode.clone();
     let fft = instance.fft.clone();
-    let show_analyzer = instance.show_analyzer.clone();
-    let en_filled_lines = instance.en_filled_lines.clone();
-    let en_filled_osc = instance.en_filled_osc.clone();
-    let stereo_view = instance.stereo_view.clone();
-    let en_left_channel = instance.en_left_channel.clone();
-    let en_right_channel = instance.en_right_channel.clone();
     let sample_rate = instance.sample_rate.clone();
     let prev_skip = instance.prev_skip.clone();
     let mut config = Ini::new();
@@ -191,9 +188,9 @@ inactive_bg = 60,60,60");
     
     create_egui_editor(
         instance.params.editor_state.clone(),
-        (),
+        AnalyzerState::new(),
         |_, _| {},
-        move |egui_ctx, setter, _state| {
+        move |egui_ctx, setter, analyzer| {
             egui::CentralPanel::default().show(egui_ctx, |ui| {
                 // Change colors - there's probably a better way to do this
                 let style_var = ui.style_mut();
@@ -244,21 +241,31 @@ Version 1.4.3");
                         ).on_hover_text("Visual gain adjustment (no output change)");
                         ui.add_space(4.0);
                         let swap_response: Response;
-                        if show_analyzer.load(Ordering::Relaxed) {
-                            let _scroll_handle = ui.add(
-                                widgets::ParamSlider::for_param(&params.scrollspeed, setter)
+                        if params.show_analyzer.value() || params.show_spectrogram.value() {
+                            // The scope's Length doesn't reach the analyzer, its resolution is the FFT size
+                            ui.add(
+                                widgets::ParamSlider::for_param(&params.fft_size, setter)
                                     .with_width(120.0),
-                            );
+                            ).on_hover_text("Samples per FFT, bigger sizes resolve lower frequencies but react slower");
                             ui.add_space(4.0);
                             
                             swap_response = ui
                                 .button("Toggle")
                                 .on_hover_text("Change the drawing order of waveforms");
                         } else {
-                            let _scroll_handle = ui.add(
-                                widgets::ParamSlider::for_param(&params.scrollspeed, setter)
-                                    .with_width(50.0),
-                            ).on_hover_text("The amount of time the oscilloscope uses to capture data");
+                            if params.long_view.value() {
+                                ui.add(
+                                    widgets::ParamSlider::for_param(&params.long_length, setter)
+                                        .with_width(50.0),
+                                ).on_hover_text("The amount of time the long view spans, drawn as a min/max envelope");
+                            } else {
+                                ui.add(
+                                    widgets::ParamSlider::for_param(&params.scrollspeed, setter)
+                                        .with_width(50.0),
+                                ).on_hover_text("The amount of time the oscilloscope uses to capture data");
+                            }
+                            ui.add(slim_checkbox::ParamSlimCheckbox::new(&params.long_view, setter, "Long"))
+                                .on_hover_text("Spans seconds to minutes instead of Length");
 
                             ui.add_space(4.0);
 
@@ -266,24 +273,41 @@ Version 1.4.3");
                                 widgets::ParamSlider::for_param(&params.h_scale, setter)
                                     .with_width(30.0),
                             ).on_hover_text("How many samples are skipped before reading a value (use this to optimize)");
+                            let _decimation_handle = ui.add(
+                                widgets::ParamSlider::for_param(&params.decimation, setter)
+                                    .with_width(40.0),
+                            ).on_hover_text("Skip keeps every Nth sample, Min/Max keeps the peaks of each group of samples");
                             ui.add_space(4.0);
                             swap_response = ui
                                 .button("Toggle")
                                 .on_hover_text("Change the drawing order of waveforms");
-                            let sync_box = slim_checkbox::AtomicSlimCheckbox::new(&sync_var, "Sync");
+                            let sync_box = slim_checkbox::ParamSlimCheckbox::new(&params.sync, setter, "Sync");
                             let sync_response = ui.add(sync_box).on_hover_text("Lock drawing to timing");
-                            let alt_sync_box = slim_checkbox::AtomicSlimCheckbox::new(&alt_sync, "Alt Sync");
+                            let alt_sync_box = slim_checkbox::ParamSlimCheckbox::new(&params.alt_sync, setter, "Alt Sync");
                             let alt_sync_response = ui.add(alt_sync_box).on_hover_text("Try this if Sync doesn't work");
                             let timing_response = ui
                                 .add(
                                     widgets::ParamSlider::for_param(&params.sync_timing, setter)
-                                        .with_width(25.0),
+                                        .with_width(40.0),
                                 )
                                 .on_hover_text("Refresh interval when sync enabled");
-                            let dir_box = slim_checkbox::AtomicSlimCheckbox::new(&dir_var, "Flip");
+                            let dir_box = slim_checkbox::ParamSlimCheckbox::new(&params.flip, setter, "Flip");
                             let dir_response = ui.add(dir_box).on_hover_text("Flip direction of oscilloscope");
-                            let fill_osc = slim_checkbox::AtomicSlimCheckbox::new(&en_filled_osc, "Fill");
+                            let fill_osc = slim_checkbox::ParamSlimCheckbox::new(&params.fill, setter, "Fill");
                             let _fill_response = ui.add(fill_osc).on_hover_text("Fill the oscilloscope drawing");
+                            let _trigger_response = ui
+                                .add(
+                                    widgets::ParamSlider::for_param(&params.trigger_mode, setter)
+                                        .with_width(40.0),
+                                )
+                                .on_hover_text("Level trigger - Auto free runs when nothing crosses, Normal waits for a crossing, Single captures once");
+                            let hold_box = slim_checkbox::AtomicSlimCheckbox::new(&hold, "Hold");
+                            let hold_response = ui.add(hold_box).on_hover_text("Freeze the capture, then drag or scroll the scope to look back through the history");
+                            if hold_response.clicked() && !hold.load(Ordering::Relaxed) {
+                                // Back to live
+                                samples.set_scrub_offset(0);
+                                samples_2.set_scrub_offset(0);
+                            }
                             // Reset our line on change
                             if sync_response.clicked()
                             || dir_response.clicked()
@@ -308,148 +332,122 @@ Version 1.4.3");
                             }
                         }
 
+                        // Only Length reaches the sample ring, the long view's length is drawn from the envelope store
                         let scroll: usize = (sample_rate.load(Ordering::Relaxed) as usize / 1000.0 as usize) * params.scrollspeed.value() as usize;
                         samples.update_internal_length(scroll);
                         samples_2.update_internal_length(scroll);
 
                         if swap_response.clicked() {
-                            let num = ontop.load(Ordering::Relaxed);
-                            // This skips possible "OFF" lines when toggling
-                            match num {
-                                0 => {
-                                    if en_aux1.load(Ordering::Relaxed) {
-                                        ontop.store(1, Ordering::Relaxed);
-                                    } else if en_aux2.load(Ordering::Relaxed) {
-                                        ontop.store(2, Ordering::Relaxed);
-                                    } else if en_aux3.load(Ordering::Relaxed) {
-                                        ontop.store(3, Ordering::Relaxed);
-                                    } else if en_aux4.load(Ordering::Relaxed) {
-                                        ontop.store(4, Ordering::Relaxed);
-                                    } else if en_aux5.load(Ordering::Relaxed) {
-                                        ontop.store(5, Ordering::Relaxed);
-                                    }
-                                }
-                                1 => {
-                                    if en_aux2.load(Ordering::Relaxed) {
-                                        ontop.store(2, Ordering::Relaxed);
-                                    } else if en_aux3.load(Ordering::Relaxed) {
-                                        ontop.store(3, Ordering::Relaxed);
-                                    } else if en_aux4.load(Ordering::Relaxed) {
-                                        ontop.store(4, Ordering::Relaxed);
-                                    } else if en_aux5.load(Ordering::Relaxed) {
-                                        ontop.store(5, Ordering::Relaxed);
-                                    } else if en_main.load(Ordering::Relaxed) {
-                                        ontop.store(0, Ordering::Relaxed);
-                                    }
-                                }
-                                2 => {
-                                    if en_aux3.load(Ordering::Relaxed) {
-                                        ontop.store(3, Ordering::Relaxed);
-                                    } else if en_aux4.load(Ordering::Relaxed) {
-                                        ontop.store(4, Ordering::Relaxed);
-                                    } else if en_aux5.load(Ordering::Relaxed) {
-                                        ontop.store(5, Ordering::Relaxed);
-                                    } else if en_main.load(Ordering::Relaxed) {
-                                        ontop.store(0, Ordering::Relaxed);
-                                    } else if en_aux1.load(Ordering::Relaxed) {
-                                        ontop.store(1, Ordering::Relaxed);
-                                    }
-                                }
-                                3 => {
-                                    if en_aux4.load(Ordering::Relaxed) {
-                                        ontop.store(4, Ordering::Relaxed);
-                                    } else if en_aux5.load(Ordering::Relaxed) {
-                                        ontop.store(5, Ordering::Relaxed);
-                                    } else if en_main.load(Ordering::Relaxed) {
-                                        ontop.store(0, Ordering::Relaxed);
-                                    } else if en_aux1.load(Ordering::Relaxed) {
-                                        ontop.store(1, Ordering::Relaxed);
-                                    } else if en_aux2.load(Ordering::Relaxed) {
-                                        ontop.store(2, Ordering::Relaxed);
-                                    }
-                                }
-                                4 => {
-                                    if en_aux5.load(Ordering::Relaxed) {
-                                        ontop.store(5, Ordering::Relaxed);
-                                    } else if en_main.load(Ordering::Relaxed) {
-                                        ontop.store(0, Ordering::Relaxed);
-                                    } else if en_aux1.load(Ordering::Relaxed) {
-                                        ontop.store(1, Ordering::Relaxed);
-                                    } else if en_aux2.load(Ordering::Relaxed) {
-                                        ontop.store(2, Ordering::Relaxed);
-                                    } else if en_aux3.load(Ordering::Relaxed) {
-                                        ontop.store(3, Ordering::Relaxed);
-                                    }
-                                }
-                                5 => {
-                                    if en_main.load(Ordering::Relaxed) {
-                                        ontop.store(0, Ordering::Relaxed);
-                                    } else if en_aux1.load(Ordering::Relaxed) {
-                                        ontop.store(1, Ordering::Relaxed);
-                                    } else if en_aux2.load(Ordering::Relaxed) {
-                                        ontop.store(2, Ordering::Relaxed);
-                                    } else if en_aux3.load(Ordering::Relaxed) {
-                                        ontop.store(3, Ordering::Relaxed);
-                                    } else if en_aux4.load(Ordering::Relaxed) {
-                                        ontop.store(4, Ordering::Relaxed);
-                                    }
-                                }
-                                _ => {
-                                    // Not reachable
-                                }
+                            // Step to the next line that's on, skipping possible "OFF" lines when toggling
+                            let enabled = params.input_channels().map(|param| param.value());
+                            let current = params.focused_line.value().to_index();
+                            if let Some(next) = (1..enabled.len()).map(|step| (current + step) % enabled.len()).find(|line| enabled[*line]) {
+                                setter.begin_set_parameter(&params.focused_line);
+                                setter.set_parameter(&params.focused_line, InputChannel::from_index(next));
+                                setter.end_set_parameter(&params.focused_line);
                             }
                         }
-                        ui.add(slim_checkbox::AtomicSlimCheckbox::new(
-                            &en_main,
+                        ui.add(slim_checkbox::ParamSlimCheckbox::new(
+                            &params.en_main,
+                            setter,
                             "In",
                         ));
-                        ui.add(slim_checkbox::AtomicSlimCheckbox::new(
-                            &en_aux1,
+                        ui.add(slim_checkbox::ParamSlimCheckbox::new(
+                            &params.en_aux1,
+                            setter,
                             "2",
                         ));
-                        ui.add(slim_checkbox::AtomicSlimCheckbox::new(
-                            &en_aux2,
+                        ui.add(slim_checkbox::ParamSlimCheckbox::new(
+                            &params.en_aux2,
+                            setter,
                             "3",
                         ));
-                        ui.add(slim_checkbox::AtomicSlimCheckbox::new(
-                            &en_aux3,
+                        ui.add(slim_checkbox::ParamSlimCheckbox::new(
+                            &params.en_aux3,
+                            setter,
                             "4",
                         ));
-                        ui.add(slim_checkbox::AtomicSlimCheckbox::new(
-                            &en_aux4,
+                        ui.add(slim_checkbox::ParamSlimCheckbox::new(
+                            &params.en_aux4,
+                            setter,
                             "5",
                         ));
-                        ui.add(slim_checkbox::AtomicSlimCheckbox::new(
-                            &en_aux5,
+                        ui.add(slim_checkbox::ParamSlimCheckbox::new(
+                            &params.en_aux5,
+                            setter,
                             "6",
                         ));
-                        if !show_analyzer.load(Ordering::Relaxed) {
-                            ui.add(slim_checkbox::AtomicSlimCheckbox::new(
-                                &en_sum,
+                        if !params.show_analyzer.value() && !params.show_spectrogram.value() {
+                            ui.add(slim_checkbox::ParamSlimCheckbox::new(
+                                &params.en_sum,
+                                setter,
                                 "Sum",
                             ));
                         }
-                        let analyzer_toggle = ui.add(slim_checkbox::AtomicSlimCheckbox::new(
-                            &show_analyzer,
+                        // The param can lag behind the click depending on the host, so remember where we started
+                        let was_analyzer = params.show_analyzer.value();
+                        let was_spectrogram = params.show_spectrogram.value();
+                        let analyzer_toggle = ui.add(slim_checkbox::ParamSlimCheckbox::new(
+                            &params.show_analyzer,
+                            setter,
                             "Analyze",
                         ));
-                        if show_analyzer.load(Ordering::Relaxed) {
-                            ui.add(slim_checkbox::AtomicSlimCheckbox::new(
-                                &en_filled_lines,
-                                "Filled Lines",
-                            ));
+                        let spectrogram_toggle = ui.add(slim_checkbox::ParamSlimCheckbox::new(
+                            &params.show_spectrogram,
+                            setter,
+                            "Spectrogram",
+                        )).on_hover_text("Scrolling time and frequency view of one input");
+                        if params.show_spectrogram.value() {
                             ui.add(slim_checkbox::AtomicSlimCheckbox::new(
                                 &en_guidelines,
                                 "Guidelines",
                             ));
+                        }
+                        if params.show_analyzer.value() {
+                            ui.add(
+                                widgets::ParamSlider::for_param(&params.analyzer_view, setter)
+                                    .with_width(60.0)
+                            ).on_hover_text("Spectrum of each input, the response of whatever sits between the reference input and main, or phase");
+                            let spectrum_view = params.analyzer_view.value() == AnalyzerView::Spectrum;
+                            if spectrum_view {
+                                ui.add(slim_checkbox::ParamSlimCheckbox::new(
+                                    &params.filled_lines,
+                                    setter,
+                                    "Filled Lines",
+                                ));
+                            }
                             ui.add(slim_checkbox::AtomicSlimCheckbox::new(
-                                &en_bar_mode,
-                                "Bar Mode",
+                                &en_guidelines,
+                                "Guidelines",
                             ));
+                            if spectrum_view {
+                                ui.add(slim_checkbox::AtomicSlimCheckbox::new(
+                                    &en_bar_mode,
+                                    "Bar Mode",
+                                ));
+                                if ui.button("References").on_hover_text("Snapshots of the spectrum to compare against").clicked() {
+                                    analyzer.references_open = !analyzer.references_open;
+                                }
+                            }
                         }
-                        if analyzer_toggle.clicked() {
-                            // This is a ! because we'll always be behind the param toggle in time
-                            if !show_analyzer.load(Ordering::Relaxed) {
+                        // Only one of the two spectral views is on at a time
+                        let was_spectral = was_analyzer || was_spectrogram;
+                        let mut is_spectral = was_spectral;
+                        for (toggle, was_on, other) in [
+                            (&analyzer_toggle, was_analyzer, &params.show_spectrogram),
+                            (&spectrogram_toggle, was_spectrogram, &params.show_analyzer),
+                        ] {
+                            if toggle.clicked() {
+                                is_spectral = !was_on;
+                                if !was_on && other.value() {
+                                    setter.begin_set_parameter(other);
+                                    setter.set_parameter(other, false);
+                                    setter.end_set_parameter(other);
+                                }
+                            }
+                        }
+                        if is_spectral != was_spectral {
+                            if was_spectral {
                                 setter.set_parameter(&params.h_scale, prev_skip.load(Ordering::Relaxed));
                             } else {
                                 prev_skip.store(params.h_scale.value(), Ordering::Relaxed);
@@ -457,7 +455,14 @@ Version 1.4.3");
                                     setter.set_parameter(&params.h_scale, 1)
                                 }
                             }
-                        }         
+                        }
+                        // Hold is only on the scope's bar, so leaving the scope lets go of it rather than
+                        // freezing a view with no way back. Also catches the host switching views
+                        let spectral = is_spectral || params.show_analyzer.value() || params.show_spectrogram.value();
+                        if spectral && hold.swap(false, Ordering::Relaxed) {
+                            samples.set_scrub_offset(0);
+                            samples_2.set_scrub_offset(0);
+                        }
                     });
                 });
 
@@ -469,7 +474,7 @@ Version 1.4.3");
                 let mut final_aux_line_color_5: Color32 = Default::default();
                 ui.allocate_ui(egui::Vec2::new(900.0, 380.0), |ui| {
                     // Fix our colors to focus on our line
-                    match ontop.load(Ordering::Relaxed) {
+                    match params.focused_line.value().to_index() {
                         0 => {
                             // Main unaffected
                             final_primary_color = primary_line_color;
@@ -529,76 +534,171 @@ Version 1.4.3");
                         }
                     }
                     // Show the frequency analyzer
-                    if show_analyzer.load(Ordering::Relaxed) {
+                    if params.show_analyzer.value() && params.analyzer_view.value() == AnalyzerView::Spectrum {
                         let mut shapes: Vec<egui::Shape> = vec![];
                         let t_sr = sr.load(Ordering::Relaxed);
-                        let scroll: usize = (t_sr as usize / 1000.0 as usize) * params.scrollspeed.value() as usize;
-                        // Sample Buffer ONE calculations
-                        // Compute our fast fourier transforms
-                        let mut buffer: Vec<Complex<f32>> = samples.get_complex_samples_with_length(0, scroll);
-                        let buffer_len: usize = buffer.len();
-                        let fft_plan: Arc<dyn Fft<f32>> = fft.lock().unwrap().plan_fft(buffer_len, FftDirection::Forward);
-                        fft_plan.process(&mut buffer);
-                        let mut ax1: Vec<Complex<f32>> = samples.get_complex_samples_with_length(1, scroll);
-                        let ax1_len: usize = ax1.len();
-                        let fft_plan: Arc<dyn Fft<f32>> = fft.lock().unwrap().plan_fft(ax1_len, FftDirection::Forward);
-                        fft_plan.process(&mut ax1);
-                        let mut ax2: Vec<Complex<f32>> = samples.get_complex_samples_with_length(2, scroll);
-                        let ax2_len: usize = ax2.len();
-                        let fft_plan: Arc<dyn Fft<f32>> = fft.lock().unwrap().plan_fft(ax2_len, FftDirection::Forward);
-                        fft_plan.process(&mut ax2);
-                        let mut ax3: Vec<Complex<f32>> = samples.get_complex_samples_with_length(3, scroll);
-                        let ax3_len: usize = ax3.len();
-                        let fft_plan: Arc<dyn Fft<f32>> = fft.lock().unwrap().plan_fft(ax3_len, FftDirection::Forward);
-                        fft_plan.process(&mut ax3);
-                        let mut ax4: Vec<Complex<f32>> = samples.get_complex_samples_with_length(4, scroll);
-                        let ax4_len: usize = ax4.len();
-                        let fft_plan: Arc<dyn Fft<f32>> = fft.lock().unwrap().plan_fft(ax4_len, FftDirection::Forward);
-                        fft_plan.process(&mut ax4);
-                        let mut ax5: Vec<Complex<f32>> = samples.get_complex_samples_with_length(5, scroll);
-                        let ax5_len: usize = ax5.len();
-                        let fft_plan: Arc<dyn Fft<f32>> = fft.lock().unwrap().plan_fft(ax5_len, FftDirection::Forward);
-                        fft_plan.process(&mut ax5);
-                        // Compute
-                        let magnitudes: Vec<f32> = buffer.iter().map(|c| c.norm() as f32).collect();
-                        let frequencies: Vec<f32> = (0..buffer_len / 2)
-                            .map(|i| i as f32 * t_sr / buffer_len as f32)
-                            .collect();
-                        let magnitudes_ax1: Vec<f32> = ax1.iter().map(|c| c.norm() as f32).collect();
-                        let frequencies_ax1: Vec<f32> = (0..ax1_len / 2)
-                            .map(|i| i as f32 * t_sr / ax1_len as f32)
-                            .collect();
-                        let magnitudes_ax2: Vec<f32> = ax2.iter().map(|c| c.norm() as f32).collect();
-                        let frequencies_ax2: Vec<f32> = (0..ax2_len / 2)
-                            .map(|i| i as f32 * t_sr / ax2_len as f32)
+                        let fft_len = analyzer.update([&samples, &samples_2], &params, &fft, t_sr);
+                        let [magnitudes, magnitudes_ax1, magnitudes_ax2, magnitudes_ax3, magnitudes_ax4, magnitudes_ax5] = &analyzer.magnitudes;
+                        let channel_colors = [final_primary_color, final_aux_line_color, final_aux_line_color_2, final_aux_line_color_3, final_aux_line_color_4, final_aux_line_color_5];
+                        // Peak hold traces follow each input's line color
+                        let peak_traces = analyzer.peaks.iter()
+                            .zip(params.input_channels())
+                            .zip(channel_colors)
+                            .filter(|((peaks, enabled), _)| params.peak_hold.value() && enabled.value() && !peaks.is_empty())
+                            .map(|((peaks, _), color)| (peaks, color));
+                        let frequencies: Vec<f32> = (0..fft_len / 2)
+                            .map(|i| i as f32 * t_sr / fft_len as f32)
                             .collect();
-                        let magnitudes_ax3: Vec<f32> = ax3.iter().map(|c| c.norm() as f32).collect();
-                        let frequencies_ax3: Vec<f32> = (0..ax3_len / 2)
-                            .map(|i| i as f32 * t_sr / ax3_len as f32)
-                            .collect();
-                        let magnitudes_ax4: Vec<f32> = ax4.iter().map(|c| c.norm() as f32).collect();
-                        let frequencies_ax4: Vec<f32> = (0..ax4_len / 2)
-                            .map(|i| i as f32 * t_sr / ax4_len as f32)
-                            .collect();
-                        let magnitudes_ax5: Vec<f32> = ax5.iter().map(|c| c.norm() as f32).collect();
-                        let frequencies_ax5: Vec<f32> = (0..ax5_len / 2)
-                            .map(|i| i as f32 * t_sr / ax5_len as f32)
-                            .collect();
-                        // Scale for visibility
-                        let db_scaler: f32 = 2.75;
-                        let freq_scaler: f32 = 285.0;
-                        let x_shift: f32 = -220.0;
-                        let y_shift: f32 = 220.0;
-                        // 1Khz pivot and -4.5 slope is same as Fruity Parametric EQ2
-                        // For some reason 12 lines up the same here...
-                        let pivot: f32 = 1000.0;
-                        let slope: f32 = 12.0;
-                        if en_bar_mode.load(Ordering::Relaxed) {
+                        // Magnitudes are dBFS, the dB range is stretched over the analyzer's height and the
+                        // frequency range, in decades, over its width
+                        let (db_min, db_max) = clamp_span(params.analyzer_db_min.value(), params.analyzer_db_max.value(), ANALYZER_DB_LIMITS, 6.0);
+                        let db_scaler: f32 = (ANALYZER_BOTTOM - ANALYZER_TOP) / (db_max - db_min);
+                        let y_shift: f32 = ANALYZER_TOP + db_max * db_scaler;
+                        let (decade_min, decade_max) = clamp_span(
+                            params.analyzer_freq_min.value().log10(),
+                            params.analyzer_freq_max.value().log10(),
+                            (ANALYZER_FREQ_LIMITS.0.log10(), ANALYZER_FREQ_LIMITS.1.log10()),
+                            0.1
+                        );
+                        let (freq_min, freq_max) = (10.0f32.powf(decade_min), 10.0f32.powf(decade_max));
+                        let freq_scaler: f32 = (ANALYZER_RIGHT - ANALYZER_LEFT) / (decade_max - decade_min);
+                        let x_shift: f32 = ANALYZER_LEFT - decade_min * freq_scaler;
+                        // Zoomed in, the spectrum is cut off at the analyzer's edges instead of running over the controls
+                        let plot_rect = Rect::from_min_max(Pos2::new(0.0, ANALYZER_TOP), Pos2::new(ui.clip_rect().right(), ANALYZER_BOTTOM));
+                        // Tilt around the pivot so the spectrum of a typical mix sits roughly level
+                        let pivot: f32 = params.tilt_pivot.value();
+                        let slope: f32 = params.tilt_slope.value().db_per_octave();
+                        if en_guidelines.load(Ordering::Relaxed) {
+                            // Labelled dB grid, in steps that keep it to about a dozen lines
+                            let db_step: f32 = [3.0, 6.0, 12.0, 24.0]
+                                .into_iter()
+                                .find(|step| (db_max - db_min) / step <= 12.0)
+                                .unwrap_or(24.0);
+                            let mut db = (db_max / db_step).floor() * db_step;
+                            while db >= db_min {
+                                let y = -db * db_scaler + y_shift;
+                                shapes.push(
+                                    epaint::Shape::line_segment(
+                                        [Pos2::new(0.0, y), Pos2::new(ui.clip_rect().right(), y)],
+                                        Stroke::new(0.5, if db == 0.0 { Color32::GRAY } else { Color32::DARK_GRAY })
+                                    )
+                                );
+                                // Right hand side, the floating controls cover the top left
+                                ui.painter().text(
+                                    Pos2::new(ui.clip_rect().right() - 4.0, y - 2.0),
+                                    Align2::RIGHT_BOTTOM,
+                                    format!("{} dB", db),
+                                    FontId::monospace(12.0),
+                                    Color32::GRAY
+                                );
+                                db -= db_step;
+                            }
+                        }
+                        // Fractional octave bars are placed on the frequency axis like the lines
+                        let bar_mode = en_bar_mode.load(Ordering::Relaxed);
+                        let octave_layout = params.bar_bands.value().bands_per_octave()
+                            .filter(|_| bar_mode)
+                            .map(|bands_per_octave| octave_bands(bands_per_octave, (freq_min, freq_max)));
+                        let noise_bandwidth = params.fft_window.value().noise_bandwidth();
+                        let bin_hz = t_sr / fft_len as f32;
+                        // Frequency guidelines wherever x is frequency, the 64 bin bars are laid out by index
+                        if en_guidelines.load(Ordering::Relaxed) && (!bar_mode || octave_layout.is_some()) {
+                            frequency_guidelines(ui.painter(), &mut shapes, freq_scaler, x_shift);
+                        }
+                        // Masking between two inputs is shaded behind the spectrum wherever they collide
+                        let masking = params.masking.value() && (!bar_mode || octave_layout.is_some());
+                        let masking_pair = (params.masking_a.value().to_index(), params.masking_b.value().to_index());
+                        let masking_threshold = params.masking_threshold.value();
+                        let masking_range = params.masking_range.value();
+                        if masking {
+                            let regions = masking_regions(
+                                &analyzer.magnitudes[masking_pair.0],
+                                &analyzer.magnitudes[masking_pair.1],
+                                bin_hz,
+                                (freq_min, freq_max),
+                                masking_threshold,
+                                masking_range,
+                                |freq: f32, magnitude: f32| pivot_frequency_slope(freq, magnitude, pivot, slope)
+                            );
+                            let shading = regions.into_iter().map(|(low, high)| {
+                                let left = low.log10() * freq_scaler + x_shift;
+                                let right = (high.log10() * freq_scaler + x_shift).max(left + 1.0);
+                                epaint::Shape::rect_filled(
+                                    Rect::from_min_max(Pos2::new(left, ANALYZER_TOP), Pos2::new(right, ANALYZER_BOTTOM)),
+                                    Rounding::none(),
+                                    Color32::RED.linear_multiply(0.15)
+                                )
+                            });
+                            ui.painter().with_clip_rect(plot_rect).extend(shading);
+                        }
+                        // Reference spectra go behind the live one, tilted the same way
+                        if !bar_mode || octave_layout.is_some() {
+                            for reference in params.references.read().unwrap().iter().filter(|reference| reference.visible) {
+                                let points = reference.levels.iter().enumerate().map(|(point, level)| {
+                                    let freq = log_frequency(point as f32 + 0.5, REFERENCE_POINTS);
+                                    (*level > util::MINUS_INFINITY_DB).then(|| {
+                                        let level = util::gain_to_db(pivot_frequency_slope(freq, util::db_to_gain(*level), pivot, slope));
+                                        pos2(freq.log10() * freq_scaler + x_shift, -level * db_scaler + y_shift)
+                                    })
+                                });
+                                let [r, g, b] = reference.color;
+                                for run in trace_runs(points, f32::INFINITY) {
+                                    shapes.push(epaint::Shape::line(run, Stroke::new(1.0, Color32::from_rgb(r, g, b))));
+                                }
+                            }
+                        }
+                        if let Some(bands) = &octave_layout {
+                            let band_rect = |band: &OctaveBand, magnitude: f32| {
+                                let level = util::gain_to_db(pivot_frequency_slope(band.centre, magnitude, pivot, slope));
+                                let left = band.low.log10() * freq_scaler + x_shift + 1.0;
+                                let right = (band.high.log10() * freq_scaler + x_shift - 1.0).max(left + 1.0);
+                                Rect { min: Pos2::new(left, -level * db_scaler + y_shift), max: Pos2::new(right, ANALYZER_BOTTOM) }
+                            };
+                            // Focused input drawn last so it's on top
+                            let focused = params.focused_line.value().to_index();
+                            for channel in (0..ANALYZER_INPUTS).rev().filter(|channel| *channel != focused).chain([focused]) {
+                                if !params.input_channels()[channel].value() {
+                                    continue;
+                                }
+                                let levels = band_levels(&analyzer.magnitudes[channel], bin_hz, bands, noise_bandwidth);
+                                for (band, magnitude) in bands.iter().zip(levels) {
+                                    shapes.push(epaint::Shape::rect_filled(band_rect(band, magnitude), Rounding::none(), channel_colors[channel]));
+                                }
+                            }
+                            // Peak hold marks across the top of each bar
+                            for (peaks, color) in peak_traces {
+                                for (band, magnitude) in bands.iter().zip(band_levels(peaks, bin_hz, bands, noise_bandwidth)) {
+                                    let top = band_rect(band, magnitude);
+                                    shapes.push(
+                                        epaint::Shape::line_segment(
+                                            [top.left_top(), top.right_top()],
+                                            Stroke::new(1.5, color)
+                                        )
+                                    );
+                                }
+                            }
+                            ui.painter().with_clip_rect(plot_rect).extend(shapes);
+                        } else if bar_mode {
+                            // Only the bins inside the frequency range are split into bars
                             let length = frequencies.len();
+                            let first_bin = ((freq_min * fft_len as f32 / t_sr) as usize).min(length.saturating_sub(1));
+                            let last_bin = ((freq_max * fft_len as f32 / t_sr).ceil() as usize).clamp(first_bin + 1, length.max(first_bin + 1));
                             //let bar_scaler = 300.0;
                             let bar_scaler: f32 = 1.6;
+                            // Bars are laid out by index across the analyzer, the frequency range picks which bins they cover
+                            let bar_shift: f32 = -220.0;
                             let bars: f32 = 64.0;
-                            let chunk_size: f32 = length as f32 / bars;
+                            let chunk_size: f32 = (last_bin - first_bin) as f32 / bars;
+                            // Zoomed in there can be fewer bins than bars, every bar still gets one
+                            let band = |i: usize| {
+                                let start = (first_bin + (i as f32 * chunk_size) as usize).min(last_bin - 1);
+                                let end = if i == bars as usize - 1 {
+                                    last_bin
+                                } else {
+                                    first_bin + ((i + 1) as f32 * chunk_size) as usize
+                                };
+                                (start, end.max(start + 1))
+                            };
                             let mut chunked_f: Vec<f32> = Vec::with_capacity(bars as usize);
                             let mut chunked_m: Vec<f32> = Vec::with_capacity(bars as usize);
                             let mut chunked_f_ax1: Vec<f32> = Vec::with_capacity(bars as usize);
@@ -611,44 +711,39 @@ Version 1.4.3");
                             let mut chunked_m_ax4: Vec<f32> = Vec::with_capacity(bars as usize);
                             let mut chunked_f_ax5: Vec<f32> = Vec::with_capacity(bars as usize);
                             let mut chunked_m_ax5: Vec<f32> = Vec::with_capacity(bars as usize);
-                            for i in 0..bars as i32 {
-                                let start = (i as f32 * chunk_size) as usize;
-                                let end = if i == bars as i32 - 1 {
-                                    length
-                                } else {
-                                    ((i + 1) as f32 * chunk_size) as usize
-                                };
+                            for i in 0..bars as usize {
+                                let (start, end) = band(i);
                                 let sum_f: f32 = frequencies[start..end].iter().sum();
                                 let average_f = sum_f / ((end - start) as f32);
                                 let sum_m: f32 = magnitudes[start..end].iter().sum();
                                 let average_m = sum_m / ((end - start) as f32);
                                 chunked_f.push(average_f);
                                 chunked_m.push(average_m);
-                                let sum_f_ax1: f32 = frequencies_ax1[start..end].iter().sum();
+                                let sum_f_ax1: f32 = frequencies[start..end].iter().sum();
                                 let average_f_ax1 = sum_f_ax1 / ((end - start) as f32);
                                 let sum_m_ax1: f32 = magnitudes_ax1[start..end].iter().sum();
                                 let average_m_ax1 = sum_m_ax1 / ((end - start) as f32);
                                 chunked_f_ax1.push(average_f_ax1);
                                 chunked_m_ax1.push(average_m_ax1);
-                                let sum_f_ax2: f32 = frequencies_ax2[start..end].iter().sum();
+                                let sum_f_ax2: f32 = frequencies[start..end].iter().sum();
                                 let average_f_ax2 = sum_f_ax2 / ((end - start) as f32);
                                 let sum_m_ax2: f32 = magnitudes_ax2[start..end].iter().sum();
                                 let average_m_ax2 = sum_m_ax2 / ((end - start) as f32);
                                 chunked_f_ax2.push(average_f_ax2);
                                 chunked_m_ax2.push(average_m_ax2);
-                                let sum_f_ax3: f32 = frequencies_ax3[start..end].iter().sum();
+                                let sum_f_ax3: f32 = frequencies[start..end].iter().sum();
                                 let average_f_ax3 = sum_f_ax3 / ((end - start) as f32);
                                 let sum_m_ax3: f32 = magnitudes_ax3[start..end].iter().sum();
                                 let average_m_ax3 = sum_m_ax3 / ((end - start) as f32);
                                 chunked_f_ax3.push(average_f_ax3);
                                 chunked_m_ax3.push(average_m_ax3);
-                                let sum_f_ax4: f32 = frequencies_ax4[start..end].iter().sum();
+                                let sum_f_ax4: f32 = frequencies[start..end].iter().sum();
                                 let average_f_ax4 = sum_f_ax4 / ((end - start) as f32);
                                 let sum_m_ax4: f32 = magnitudes_ax4[start..end].iter().sum();
                                 let average_m_ax4 = sum_m_ax4 / ((end - start) as f32);
                                 chunked_f_ax4.push(average_f_ax4);
                                 chunked_m_ax4.push(average_m_ax4);
-                                let sum_f_ax5: f32 = frequencies_ax5[start..end].iter().sum();
+                                let sum_f_ax5: f32 = frequencies[start..end].iter().sum();
                                 let average_f_ax5 = sum_f_ax5 / ((end - start) as f32);
                                 let sum_m_ax5: f32 = magnitudes_ax5[start..end].iter().sum();
                                 let average_m_ax5 = sum_m_ax5 / ((end - start) as f32);
@@ -730,85 +825,85 @@ Version 1.4.3");
                                 })
                                 .collect();
                                 // Draw whichever order next
-                                match ontop.load(Ordering::Relaxed) {
+                                match params.focused_line.value().to_index() {
                                     0 => {
-                                        if en_aux5.load(Ordering::Relaxed) { 
+                                        if params.en_aux5.value() { 
                                             for elem in data_ax5.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_5
+                                                        final_aux_line_color_5
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_aux4.load(Ordering::Relaxed) { 
+                                        if params.en_aux4.value() { 
                                             for elem in data_ax4.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_4
+                                                        final_aux_line_color_4
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_aux3.load(Ordering::Relaxed) { 
+                                        if params.en_aux3.value() { 
                                             for elem in data_ax3.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_3
+                                                        final_aux_line_color_3
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_aux2.load(Ordering::Relaxed) { 
+                                        if params.en_aux2.value() { 
                                             for elem in data_ax2.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_2
+                                                        final_aux_line_color_2
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_aux1.load(Ordering::Relaxed) { 
+                                        if params.en_aux1.value() { 
                                             for elem in data_ax1.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_1
+                                                        final_aux_line_color
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_main.load(Ordering::Relaxed) { 
+                                        if params.en_main.value() { 
                                             for elem in data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
                                                         final_primary_color
@@ -818,13 +913,13 @@ Version 1.4.3");
                                         }
                                     }
                                     1 => {
-                                        if en_main.load(Ordering::Relaxed) { 
+                                        if params.en_main.value() { 
                                             for elem in data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
                                                         final_primary_color
@@ -832,99 +927,99 @@ Version 1.4.3");
                                                 );
                                             }
                                         }
-                                        if en_aux5.load(Ordering::Relaxed) { 
+                                        if params.en_aux5.value() { 
                                             for elem in data_ax5.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_5
+                                                        final_aux_line_color_5
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_aux4.load(Ordering::Relaxed) { 
+                                        if params.en_aux4.value() { 
                                             for elem in data_ax4.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_4
+                                                        final_aux_line_color_4
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_aux3.load(Ordering::Relaxed) { 
+                                        if params.en_aux3.value() { 
                                             for elem in data_ax3.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_3
+                                                        final_aux_line_color_3
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_aux2.load(Ordering::Relaxed) { 
+                                        if params.en_aux2.value() { 
                                             for elem in data_ax2.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_2
+                                                        final_aux_line_color_2
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_aux1.load(Ordering::Relaxed) { 
+                                        if params.en_aux1.value() { 
                                             for elem in data_ax1.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_1
+                                                        final_aux_line_color
                                                     )
                                                 );
                                             }
                                         }
                                     }
                                     2 => {
-                                        if en_aux1.load(Ordering::Relaxed) { 
+                                        if params.en_aux1.value() { 
                                             for elem in data_ax1.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_1
+                                                        final_aux_line_color
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_main.load(Ordering::Relaxed) { 
+                                        if params.en_main.value() { 
                                             for elem in data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
                                                         final_primary_color
@@ -932,99 +1027,99 @@ Version 1.4.3");
                                                 );
                                             }
                                         }
-                                        if en_aux5.load(Ordering::Relaxed) { 
+                                        if params.en_aux5.value() { 
                                             for elem in data_ax5.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_5
+                                                        final_aux_line_color_5
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_aux4.load(Ordering::Relaxed) { 
+                                        if params.en_aux4.value() { 
                                             for elem in data_ax4.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_4
+                                                        final_aux_line_color_4
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_aux3.load(Ordering::Relaxed) { 
+                                        if params.en_aux3.value() { 
                                             for elem in data_ax3.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_3
+                                                        final_aux_line_color_3
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_aux2.load(Ordering::Relaxed) { 
+                                        if params.en_aux2.value() { 
                                             for elem in data_ax2.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_2
+                                                        final_aux_line_color_2
                                                     )
                                                 );
                                             }
                                         }
                                     }
                                     3 => {
-                                        if en_aux2.load(Ordering::Relaxed) { 
+                                        if params.en_aux2.value() { 
                                             for elem in data_ax2.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_2
+                                                        final_aux_line_color_2
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_aux1.load(Ordering::Relaxed) { 
+                                        if params.en_aux1.value() { 
                                             for elem in data_ax1.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_1
+                                                        final_aux_line_color
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_main.load(Ordering::Relaxed) { 
+                                        if params.en_main.value() { 
                                             for elem in data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
                                                         final_primary_color
@@ -1032,99 +1127,99 @@ Version 1.4.3");
                                                 );
                                             }
                                         }
-                                        if en_aux5.load(Ordering::Relaxed) { 
+                                        if params.en_aux5.value() { 
                                             for elem in data_ax5.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_5
+                                                        final_aux_line_color_5
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_aux4.load(Ordering::Relaxed) { 
+                                        if params.en_aux4.value() { 
                                             for elem in data_ax4.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_4
+                                                        final_aux_line_color_4
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_aux3.load(Ordering::Relaxed) { 
+                                        if params.en_aux3.value() { 
                                             for elem in data_ax3.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_3
+                                                        final_aux_line_color_3
                                                     )
                                                 );
                                             }
                                         }
                                     }
                                     4 => {
-                                        if en_aux3.load(Ordering::Relaxed) { 
+                                        if params.en_aux3.value() { 
                                             for elem in data_ax3.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_3
+                                                        final_aux_line_color_3
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_aux2.load(Ordering::Relaxed) { 
+                                        if params.en_aux2.value() { 
                                             for elem in data_ax2.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_2
+                                                        final_aux_line_color_2
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_aux1.load(Ordering::Relaxed) { 
+                                        if params.en_aux1.value() { 
                                             for elem in data_ax1.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_1
+                                                        final_aux_line_color
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_main.load(Ordering::Relaxed) { 
+                                        if params.en_main.value() { 
                                             for elem in data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
                                                         final_primary_color
@@ -1132,99 +1227,99 @@ Version 1.4.3");
                                                 );
                                             }
                                         }
-                                        if en_aux5.load(Ordering::Relaxed) { 
+                                        if params.en_aux5.value() { 
                                             for elem in data_ax5.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_5
+                                                        final_aux_line_color_5
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_aux4.load(Ordering::Relaxed) { 
+                                        if params.en_aux4.value() { 
                                             for elem in data_ax4.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_4
+                                                        final_aux_line_color_4
                                                     )
                                                 );
                                             }
                                         }
                                     }
                                     5 => {
-                                        if en_aux4.load(Ordering::Relaxed) { 
+                                        if params.en_aux4.value() { 
                                             for elem in data_ax4.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_4
+                                                        final_aux_line_color_4
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_aux3.load(Ordering::Relaxed) { 
+                                        if params.en_aux3.value() { 
                                             for elem in data_ax3.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_3
+                                                        final_aux_line_color_3
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_aux2.load(Ordering::Relaxed) { 
+                                        if params.en_aux2.value() { 
                                             for elem in data_ax2.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_2
+                                                        final_aux_line_color_2
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_aux1.load(Ordering::Relaxed) { 
+                                        if params.en_aux1.value() { 
                                             for elem in data_ax1.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_1
+                                                        final_aux_line_color
                                                     )
                                                 );
                                             }
                                         }
-                                        if en_main.load(Ordering::Relaxed) { 
+                                        if params.en_main.value() { 
                                             for elem in data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
                                                         final_primary_color
@@ -1232,16 +1327,16 @@ Version 1.4.3");
                                                 );
                                             }
                                         }
-                                        if en_aux5.load(Ordering::Relaxed) { 
+                                        if params.en_aux5.value() { 
                                             for elem in data_ax5.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
                                                         Rect { 
-                                                            min: Pos2::new(elem.x + x_shift, elem.y), 
-                                                            max: Pos2::new(elem.x + 10.0 + x_shift, 515.0)
+                                                            min: Pos2::new(elem.x + bar_shift, elem.y), 
+                                                            max: Pos2::new(elem.x + 10.0 + bar_shift, 515.0)
                                                         },
                                                         Rounding::none(),
-                                                        user_aux_5
+                                                        final_aux_line_color_5
                                                     )
                                                 );
                                             }
@@ -1251,7 +1346,23 @@ Version 1.4.3");
                                         // We shouldn't be here
                                     }
                                 }
-                            ui.painter().extend(shapes);
+                            // Peak hold marks across the top of each bar
+                            for (peaks, color) in peak_traces {
+                                for (i, freq) in chunked_f.iter().enumerate() {
+                                    let (start, end) = band(i);
+                                    let average_peak = peaks[start..end].iter().sum::<f32>() / ((end - start) as f32);
+                                    let y = pivot_frequency_slope(*freq, average_peak, pivot, slope);
+                                    let x = i as f32 * 10.0 * bar_scaler + 230.0 + bar_shift;
+                                    let y = (util::gain_to_db(y) * -1.0) * db_scaler + y_shift;
+                                    shapes.push(
+                                        epaint::Shape::line_segment(
+                                            [Pos2::new(x, y), Pos2::new(x + 10.0, y)],
+                                            Stroke::new(1.5, color)
+                                        )
+                                    );
+                                }
+                            }
+                            ui.painter().with_clip_rect(plot_rect).extend(shapes);
                         } else {
                             // Primary Input
                             let data: Vec<Pos2> = frequencies
@@ -1266,7 +1377,7 @@ Version 1.4.3");
                                 })
                                 .collect();
                             // Aux
-                            let ax1_data: Vec<Pos2> = frequencies_ax1
+                            let ax1_data: Vec<Pos2> = frequencies
                                 .iter()
                                 .zip(magnitudes_ax1.iter())
                                 .map(|(freq, magnitude)| {
@@ -1277,7 +1388,7 @@ Version 1.4.3");
                                     )
                                 })
                                 .collect();
-                            let ax2_data: Vec<Pos2> = frequencies_ax2
+                            let ax2_data: Vec<Pos2> = frequencies
                                 .iter()
                                 .zip(magnitudes_ax2.iter())
                                 .map(|(freq, magnitude)| {
@@ -1288,7 +1399,7 @@ Version 1.4.3");
                                     )
                                 })
                                 .collect();
-                            let ax3_data: Vec<Pos2> = frequencies_ax3
+                            let ax3_data: Vec<Pos2> = frequencies
                                 .iter()
                                 .zip(magnitudes_ax3.iter())
                                 .map(|(freq, magnitude)| {
@@ -1299,7 +1410,7 @@ Version 1.4.3");
                                     )
                                 })
                                 .collect();
-                            let ax4_data: Vec<Pos2> = frequencies_ax4
+                            let ax4_data: Vec<Pos2> = frequencies
                                 .iter()
                                 .zip(magnitudes_ax4.iter())
                                 .map(|(freq, magnitude)| {
@@ -1310,7 +1421,7 @@ Version 1.4.3");
                                     )
                                 })
                                 .collect();
-                            let ax5_data: Vec<Pos2> = frequencies_ax5
+                            let ax5_data: Vec<Pos2> = frequencies
                                 .iter()
                                 .zip(magnitudes_ax5.iter())
                                 .map(|(freq, magnitude)| {
@@ -1321,48 +1432,26 @@ Version 1.4.3");
                                     )
                                 })
                                 .collect();
-                            if en_guidelines.load(Ordering::Relaxed) {
-                                let freqs: [f32; 12] = [
-                                    0.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0, 1000.0, 2000.0, 5000.0, 10000.0, 18000.0];
-                                let scaled_ref_freqs: Vec<f32> = freqs.iter().map(|num|{num.log10() * freq_scaler}).collect();
-                                for (num,scaled_num) in freqs.iter().zip(scaled_ref_freqs.iter()) {
-                                    shapes.push(
-                                        epaint::Shape::line_segment(
-                                            [
-                                                Pos2::new(*scaled_num + x_shift, 515.0),
-                                                Pos2::new(*scaled_num + x_shift, 30.0)
-                                            ],
-                                            Stroke::new(0.5, Color32::GRAY)
-                                        )
-                                    );
-                                    ui.painter().text(
-                                        Pos2::new(scaled_num + 2.0 + x_shift, 510.0), 
-                                        Align2::LEFT_CENTER, 
-                                        *num, 
-                                        FontId::monospace(12.0), 
-                                        Color32::GRAY
-                                    );
-                                }
-                                let sub_freqs: [f32; 18] = [
-                                    30.0,40.0,60.0,70.0,80.0,90.0,300.0,400.0,600.0,700.0,800.0,900.0,3000.0,4000.0,6000.0,7000.0,8000.0,9000.0];
-                                let scaled_sub_freqs: Vec<f32> = sub_freqs.iter().map(|num|{num.log10() * freq_scaler}).collect();
-                                for scaled_num in scaled_sub_freqs.iter() {
-                                    shapes.push(
-                                        epaint::Shape::line_segment(
-                                            [
-                                                Pos2::new(*scaled_num + x_shift, 515.0),
-                                                Pos2::new(*scaled_num + x_shift, 30.0)
-                                            ],
-                                            Stroke::new(0.5, Color32::DARK_GRAY)
+                            // Peak hold traces sit behind the live lines
+                            for (peaks, color) in peak_traces {
+                                let peak_data: Vec<Pos2> = frequencies
+                                    .iter()
+                                    .zip(peaks.iter())
+                                    .map(|(freq, magnitude)| {
+                                        let y = pivot_frequency_slope(*freq, *magnitude, pivot, slope);
+                                        pos2(
+                                            freq.log10() * freq_scaler + x_shift,
+                                            (util::gain_to_db(y) * -1.0) * db_scaler + y_shift
                                         )
-                                    );
-                                }
+                                    })
+                                    .collect();
+                                shapes.push(epaint::Shape::line(peak_data, Stroke::new(0.75, color)));
                             }
                             // Draw whichever order next
-                            match ontop.load(Ordering::Relaxed) {
+                            match params.focused_line.value().to_index() {
                                 0 => {
-                                    if en_filled_lines.load(Ordering::Relaxed) {
-                                        if en_aux5.load(Ordering::Relaxed) { 
+                                    if params.filled_lines.value() {
+                                        if params.en_aux5.value() { 
                                             for point in ax5_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1376,7 +1465,7 @@ Version 1.4.3");
                                                 );
                                             }  
                                         }
-                                        if en_aux4.load(Ordering::Relaxed) { 
+                                        if params.en_aux4.value() { 
                                             for point in ax4_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1390,7 +1479,7 @@ Version 1.4.3");
                                                 );
                                             }  
                                         }
-                                        if en_aux3.load(Ordering::Relaxed) { 
+                                        if params.en_aux3.value() { 
                                             for point in ax3_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1404,7 +1493,7 @@ Version 1.4.3");
                                                 );
                                             }  
                                         }
-                                        if en_aux2.load(Ordering::Relaxed) { 
+                                        if params.en_aux2.value() { 
                                             for point in ax2_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1418,7 +1507,7 @@ Version 1.4.3");
                                                 );
                                             }  
                                         }
-                                        if en_aux1.load(Ordering::Relaxed) { 
+                                        if params.en_aux1.value() { 
                                             for point in ax1_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1432,7 +1521,7 @@ Version 1.4.3");
                                                 );
                                             }    
                                         }
-                                        if en_main.load(Ordering::Relaxed) {
+                                        if params.en_main.value() {
                                             for point in data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1447,17 +1536,17 @@ Version 1.4.3");
                                             }
                                         }
                                     } else {
-                                        if en_aux5.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax5_data, Stroke::new(1.0, final_aux_line_color_5))); }
-                                        if en_aux4.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax4_data, Stroke::new(1.0, final_aux_line_color_4))); }
-                                        if en_aux3.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax3_data, Stroke::new(1.0, final_aux_line_color_3))); }
-                                        if en_aux2.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax2_data, Stroke::new(1.0, final_aux_line_color_2))); }
-                                        if en_aux1.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax1_data, Stroke::new(1.0, final_aux_line_color))); }
-                                        if en_main.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(data, Stroke::new(1.0, final_primary_color))); }
+                                        if params.en_aux5.value() { shapes.push(epaint::Shape::line(ax5_data, Stroke::new(1.0, final_aux_line_color_5))); }
+                                        if params.en_aux4.value() { shapes.push(epaint::Shape::line(ax4_data, Stroke::new(1.0, final_aux_line_color_4))); }
+                                        if params.en_aux3.value() { shapes.push(epaint::Shape::line(ax3_data, Stroke::new(1.0, final_aux_line_color_3))); }
+                                        if params.en_aux2.value() { shapes.push(epaint::Shape::line(ax2_data, Stroke::new(1.0, final_aux_line_color_2))); }
+                                        if params.en_aux1.value() { shapes.push(epaint::Shape::line(ax1_data, Stroke::new(1.0, final_aux_line_color))); }
+                                        if params.en_main.value() { shapes.push(epaint::Shape::line(data, Stroke::new(1.0, final_primary_color))); }
                                     }
                                 }
                                 1 => {
-                                    if en_filled_lines.load(Ordering::Relaxed) {
-                                        if en_main.load(Ordering::Relaxed) {
+                                    if params.filled_lines.value() {
+                                        if params.en_main.value() {
                                             for point in data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1471,7 +1560,7 @@ Version 1.4.3");
                                                 );
                                             }
                                         }
-                                        if en_aux5.load(Ordering::Relaxed) { 
+                                        if params.en_aux5.value() { 
                                             for point in ax5_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1485,7 +1574,7 @@ Version 1.4.3");
                                                 );
                                             }  
                                         }
-                                        if en_aux4.load(Ordering::Relaxed) { 
+                                        if params.en_aux4.value() { 
                                             for point in ax4_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1499,7 +1588,7 @@ Version 1.4.3");
                                                 );
                                             }  
                                         }
-                                        if en_aux3.load(Ordering::Relaxed) { 
+                                        if params.en_aux3.value() { 
                                             for point in ax3_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1513,7 +1602,7 @@ Version 1.4.3");
                                                 );
                                             }  
                                         }
-                                        if en_aux2.load(Ordering::Relaxed) { 
+                                        if params.en_aux2.value() { 
                                             for point in ax2_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1527,7 +1616,7 @@ Version 1.4.3");
                                                 );
                                             }  
                                         }
-                                        if en_aux1.load(Ordering::Relaxed) { 
+                                        if params.en_aux1.value() { 
                                             for point in ax1_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1542,17 +1631,17 @@ Version 1.4.3");
                                             }    
                                         }
                                     } else {
-                                        if en_main.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(data, Stroke::new(1.0, final_primary_color))); }
-                                        if en_aux5.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax5_data, Stroke::new(1.0, final_aux_line_color_5))); }
-                                        if en_aux4.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax4_data, Stroke::new(1.0, final_aux_line_color_4))); }
-                                        if en_aux3.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax3_data, Stroke::new(1.0, final_aux_line_color_3))); }
-                                        if en_aux2.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax2_data, Stroke::new(1.0, final_aux_line_color_2))); }
-                                        if en_aux1.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax1_data, Stroke::new(1.0, final_aux_line_color))); }
+                                        if params.en_main.value() { shapes.push(epaint::Shape::line(data, Stroke::new(1.0, final_primary_color))); }
+                                        if params.en_aux5.value() { shapes.push(epaint::Shape::line(ax5_data, Stroke::new(1.0, final_aux_line_color_5))); }
+                                        if params.en_aux4.value() { shapes.push(epaint::Shape::line(ax4_data, Stroke::new(1.0, final_aux_line_color_4))); }
+                                        if params.en_aux3.value() { shapes.push(epaint::Shape::line(ax3_data, Stroke::new(1.0, final_aux_line_color_3))); }
+                                        if params.en_aux2.value() { shapes.push(epaint::Shape::line(ax2_data, Stroke::new(1.0, final_aux_line_color_2))); }
+                                        if params.en_aux1.value() { shapes.push(epaint::Shape::line(ax1_data, Stroke::new(1.0, final_aux_line_color))); }
                                     }
                                 }
                                 2 => {
-                                    if en_filled_lines.load(Ordering::Relaxed) {
-                                        if en_aux1.load(Ordering::Relaxed) { 
+                                    if params.filled_lines.value() {
+                                        if params.en_aux1.value() { 
                                             for point in ax1_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1566,7 +1655,7 @@ Version 1.4.3");
                                                 );
                                             }    
                                         }
-                                        if en_main.load(Ordering::Relaxed) {
+                                        if params.en_main.value() {
                                             for point in data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1580,7 +1669,7 @@ Version 1.4.3");
                                                 );
                                             }
                                         }
-                                        if en_aux5.load(Ordering::Relaxed) { 
+                                        if params.en_aux5.value() { 
                                             for point in ax5_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1594,7 +1683,7 @@ Version 1.4.3");
                                                 );
                                             }  
                                         }
-                                        if en_aux4.load(Ordering::Relaxed) { 
+                                        if params.en_aux4.value() { 
                                             for point in ax4_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1608,7 +1697,7 @@ Version 1.4.3");
                                                 );
                                             }  
                                         }
-                                        if en_aux3.load(Ordering::Relaxed) { 
+                                        if params.en_aux3.value() { 
                                             for point in ax3_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1622,7 +1711,7 @@ Version 1.4.3");
                                                 );
                                             }  
                                         }
-                                        if en_aux2.load(Ordering::Relaxed) { 
+                                        if params.en_aux2.value() { 
                                             for point in ax2_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1637,17 +1726,17 @@ Version 1.4.3");
                                             }  
                                         }
                                     } else {
-                                        if en_aux1.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax1_data, Stroke::new(1.0, final_aux_line_color))); }
-                                        if en_main.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(data, Stroke::new(1.0, final_primary_color))); }
-                                        if en_aux5.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax5_data, Stroke::new(1.0, final_aux_line_color_5))); }
-                                        if en_aux4.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax4_data, Stroke::new(1.0, final_aux_line_color_4))); }
-                                        if en_aux3.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax3_data, Stroke::new(1.0, final_aux_line_color_3))); }
-                                        if en_aux2.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax2_data, Stroke::new(1.0, final_aux_line_color_2))); }
+                                        if params.en_aux1.value() { shapes.push(epaint::Shape::line(ax1_data, Stroke::new(1.0, final_aux_line_color))); }
+                                        if params.en_main.value() { shapes.push(epaint::Shape::line(data, Stroke::new(1.0, final_primary_color))); }
+                                        if params.en_aux5.value() { shapes.push(epaint::Shape::line(ax5_data, Stroke::new(1.0, final_aux_line_color_5))); }
+                                        if params.en_aux4.value() { shapes.push(epaint::Shape::line(ax4_data, Stroke::new(1.0, final_aux_line_color_4))); }
+                                        if params.en_aux3.value() { shapes.push(epaint::Shape::line(ax3_data, Stroke::new(1.0, final_aux_line_color_3))); }
+                                        if params.en_aux2.value() { shapes.push(epaint::Shape::line(ax2_data, Stroke::new(1.0, final_aux_line_color_2))); }
                                     }
                                 }
                                 3 => {
-                                    if en_filled_lines.load(Ordering::Relaxed) {
-                                        if en_aux2.load(Ordering::Relaxed) { 
+                                    if params.filled_lines.value() {
+                                        if params.en_aux2.value() { 
                                             for point in ax2_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1661,7 +1750,7 @@ Version 1.4.3");
                                                 );
                                             }  
                                         }
-                                        if en_aux1.load(Ordering::Relaxed) { 
+                                        if params.en_aux1.value() { 
                                             for point in ax1_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1675,7 +1764,7 @@ Version 1.4.3");
                                                 );
                                             }    
                                         }
-                                        if en_main.load(Ordering::Relaxed) {
+                                        if params.en_main.value() {
                                             for point in data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1689,7 +1778,7 @@ Version 1.4.3");
                                                 );
                                             }
                                         }
-                                        if en_aux5.load(Ordering::Relaxed) { 
+                                        if params.en_aux5.value() { 
                                             for point in ax5_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1703,7 +1792,7 @@ Version 1.4.3");
                                                 );
                                             }  
                                         }
-                                        if en_aux4.load(Ordering::Relaxed) { 
+                                        if params.en_aux4.value() { 
                                             for point in ax4_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1717,7 +1806,7 @@ Version 1.4.3");
                                                 );
                                             }  
                                         }
-                                        if en_aux3.load(Ordering::Relaxed) { 
+                                        if params.en_aux3.value() { 
                                             for point in ax3_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1732,17 +1821,17 @@ Version 1.4.3");
                                             }  
                                         }
                                     } else {
-                                        if en_aux2.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax2_data, Stroke::new(1.0, final_aux_line_color_2))); }
-                                        if en_aux1.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax1_data, Stroke::new(1.0, final_aux_line_color))); }
-                                        if en_main.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(data, Stroke::new(1.0, final_primary_color))); }
-                                        if en_aux5.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax5_data, Stroke::new(1.0, final_aux_line_color_5))); }
-                                        if en_aux4.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax4_data, Stroke::new(1.0, final_aux_line_color_4))); }
-                                        if en_aux3.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax3_data, Stroke::new(1.0, final_aux_line_color_3))); }
+                                        if params.en_aux2.value() { shapes.push(epaint::Shape::line(ax2_data, Stroke::new(1.0, final_aux_line_color_2))); }
+                                        if params.en_aux1.value() { shapes.push(epaint::Shape::line(ax1_data, Stroke::new(1.0, final_aux_line_color))); }
+                                        if params.en_main.value() { shapes.push(epaint::Shape::line(data, Stroke::new(1.0, final_primary_color))); }
+                                        if params.en_aux5.value() { shapes.push(epaint::Shape::line(ax5_data, Stroke::new(1.0, final_aux_line_color_5))); }
+                                        if params.en_aux4.value() { shapes.push(epaint::Shape::line(ax4_data, Stroke::new(1.0, final_aux_line_color_4))); }
+                                        if params.en_aux3.value() { shapes.push(epaint::Shape::line(ax3_data, Stroke::new(1.0, final_aux_line_color_3))); }
                                     }
                                 }
                                 4 => {
-                                    if en_filled_lines.load(Ordering::Relaxed) {
-                                        if en_aux3.load(Ordering::Relaxed) { 
+                                    if params.filled_lines.value() {
+                                        if params.en_aux3.value() { 
                                             for point in ax3_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1756,7 +1845,7 @@ Version 1.4.3");
                                                 );
                                             }  
                                         }
-                                        if en_aux2.load(Ordering::Relaxed) { 
+                                        if params.en_aux2.value() { 
                                             for point in ax2_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1770,7 +1859,7 @@ Version 1.4.3");
                                                 );
                                             }  
                                         }
-                                        if en_aux1.load(Ordering::Relaxed) { 
+                                        if params.en_aux1.value() { 
                                             for point in ax1_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1784,7 +1873,7 @@ Version 1.4.3");
                                                 );
                                             }    
                                         }
-                                        if en_main.load(Ordering::Relaxed) {
+                                        if params.en_main.value() {
                                             for point in data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1798,7 +1887,7 @@ Version 1.4.3");
                                                 );
                                             }
                                         }
-                                        if en_aux5.load(Ordering::Relaxed) { 
+                                        if params.en_aux5.value() { 
                                             for point in ax5_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1812,7 +1901,7 @@ Version 1.4.3");
                                                 );
                                             }  
                                         }
-                                        if en_aux4.load(Ordering::Relaxed) { 
+                                        if params.en_aux4.value() { 
                                             for point in ax4_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1827,17 +1916,17 @@ Version 1.4.3");
                                             }  
                                         }
                                     } else {
-                                        if en_aux3.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax3_data, Stroke::new(1.0, final_aux_line_color_3))); }
-                                        if en_aux2.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax2_data, Stroke::new(1.0, final_aux_line_color_2))); }
-                                        if en_aux1.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax1_data, Stroke::new(1.0, final_aux_line_color))); }
-                                        if en_main.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(data, Stroke::new(1.0, final_primary_color))); }
-                                        if en_aux5.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax5_data, Stroke::new(1.0, final_aux_line_color_5))); }
-                                        if en_aux4.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax4_data, Stroke::new(1.0, final_aux_line_color_4))); }
+                                        if params.en_aux3.value() { shapes.push(epaint::Shape::line(ax3_data, Stroke::new(1.0, final_aux_line_color_3))); }
+                                        if params.en_aux2.value() { shapes.push(epaint::Shape::line(ax2_data, Stroke::new(1.0, final_aux_line_color_2))); }
+                                        if params.en_aux1.value() { shapes.push(epaint::Shape::line(ax1_data, Stroke::new(1.0, final_aux_line_color))); }
+                                        if params.en_main.value() { shapes.push(epaint::Shape::line(data, Stroke::new(1.0, final_primary_color))); }
+                                        if params.en_aux5.value() { shapes.push(epaint::Shape::line(ax5_data, Stroke::new(1.0, final_aux_line_color_5))); }
+                                        if params.en_aux4.value() { shapes.push(epaint::Shape::line(ax4_data, Stroke::new(1.0, final_aux_line_color_4))); }
                                     }
                                 }
                                 5 => {
-                                    if en_filled_lines.load(Ordering::Relaxed) {
-                                        if en_aux4.load(Ordering::Relaxed) { 
+                                    if params.filled_lines.value() {
+                                        if params.en_aux4.value() { 
                                             for point in ax4_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1851,7 +1940,7 @@ Version 1.4.3");
                                                 );
                                             }  
                                         }
-                                        if en_aux3.load(Ordering::Relaxed) { 
+                                        if params.en_aux3.value() { 
                                             for point in ax3_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1865,7 +1954,7 @@ Version 1.4.3");
                                                 );
                                             }  
                                         }
-                                        if en_aux2.load(Ordering::Relaxed) { 
+                                        if params.en_aux2.value() { 
                                             for point in ax2_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1879,7 +1968,7 @@ Version 1.4.3");
                                                 );
                                             }  
                                         }
-                                        if en_aux1.load(Ordering::Relaxed) { 
+                                        if params.en_aux1.value() { 
                                             for point in ax1_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1893,7 +1982,7 @@ Version 1.4.3");
                                                 );
                                             }    
                                         }
-                                        if en_main.load(Ordering::Relaxed) {
+                                        if params.en_main.value() {
                                             for point in data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1907,7 +1996,7 @@ Version 1.4.3");
                                                 );
                                             }
                                         }
-                                        if en_aux5.load(Ordering::Relaxed) { 
+                                        if params.en_aux5.value() { 
                                             for point in ax5_data.iter() {
                                                 shapes.push(
                                                     epaint::Shape::rect_filled(
@@ -1922,32 +2011,581 @@ Version 1.4.3");
                                             }  
                                         }
                                     } else {
-                                        if en_aux4.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax4_data, Stroke::new(1.0, final_aux_line_color_4))); }
-                                        if en_aux3.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax3_data, Stroke::new(1.0, final_aux_line_color_3))); }
-                                        if en_aux2.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax2_data, Stroke::new(1.0, final_aux_line_color_2))); }
-                                        if en_aux1.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax1_data, Stroke::new(1.0, final_aux_line_color))); }
-                                        if en_main.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(data, Stroke::new(1.0, final_primary_color))); }
-                                        if en_aux5.load(Ordering::Relaxed) { shapes.push(epaint::Shape::line(ax5_data, Stroke::new(1.0, final_aux_line_color_5))); }
+                                        if params.en_aux4.value() { shapes.push(epaint::Shape::line(ax4_data, Stroke::new(1.0, final_aux_line_color_4))); }
+                                        if params.en_aux3.value() { shapes.push(epaint::Shape::line(ax3_data, Stroke::new(1.0, final_aux_line_color_3))); }
+                                        if params.en_aux2.value() { shapes.push(epaint::Shape::line(ax2_data, Stroke::new(1.0, final_aux_line_color_2))); }
+                                        if params.en_aux1.value() { shapes.push(epaint::Shape::line(ax1_data, Stroke::new(1.0, final_aux_line_color))); }
+                                        if params.en_main.value() { shapes.push(epaint::Shape::line(data, Stroke::new(1.0, final_primary_color))); }
+                                        if params.en_aux5.value() { shapes.push(epaint::Shape::line(ax5_data, Stroke::new(1.0, final_aux_line_color_5))); }
                                     }
                                 }
                                 _ => {
                                     // We shouldn't be here
                                 }
                             }
-                            ui.painter().extend(shapes);
+                            ui.painter().with_clip_rect(plot_rect).extend(shapes);
+                        }
+
+                        // Loudest peaks of each channel, over the lines or the octave bars. Labels that would overlap one
+                        // already placed are left out
+                        let peak_count = params.peak_labels.value() as usize;
+                        if peak_count > 0 && (!bar_mode || octave_layout.is_some()) {
+                            let mut placed: Vec<Rect> = Vec::new();
+                            let tilted = |freq: f32, magnitude: f32| pivot_frequency_slope(freq, magnitude, pivot, slope);
+                            for ((channel_magnitudes, enabled), color) in analyzer.magnitudes.iter().zip(params.input_channels()).zip(channel_colors) {
+                                if !enabled.value() {
+                                    continue;
+                                }
+                                let below_nyquist = &channel_magnitudes[..channel_magnitudes.len() / 2];
+                                for peak in find_peaks(below_nyquist, bin_hz, (freq_min, freq_max), peak_count, tilted) {
+                                    let level = util::gain_to_db(tilted(peak.freq, peak.magnitude));
+                                    let position = pos2(peak.freq.log10() * freq_scaler + x_shift, -level * db_scaler + y_shift);
+                                    if !plot_rect.contains(position) {
+                                        continue;
+                                    }
+                                    let (note, cents) = note_name(peak.freq);
+                                    let galley = ui.painter().layout_no_wrap(
+                                        format!("{} {} {:+.0}c {:.1} dB", frequency_label(peak.freq), note, cents, level),
+                                        FontId::monospace(11.0),
+                                        color
+                                    );
+                                    let label = Rect::from_min_size(position + egui::vec2(-galley.size().x / 2.0, -galley.size().y - 6.0), galley.size());
+                                    ui.painter().circle_filled(position, 2.5, color);
+                                    if placed.iter().all(|other| !other.intersects(label)) {
+                                        placed.push(label);
+                                        ui.painter().galley(label.min, galley);
+                                    }
+                                }
+                            }
+                        }
+                        // Collision score of each band under the controls, in the Bar Mode bands or thirds of an octave
+                        if masking {
+                            let third_octaves;
+                            let bands = match &octave_layout {
+                                Some(bands) => bands,
+                                None => {
+                                    third_octaves = octave_bands(3, (freq_min, freq_max));
+                                    &third_octaves
+                                }
+                            };
+                            let tilted_levels = |channel: usize| -> Vec<f32> {
+                                bands
+                                    .iter()
+                                    .zip(band_levels(&analyzer.magnitudes[channel], bin_hz, bands, noise_bandwidth))
+                                    .map(|(band, magnitude)| util::gain_to_db(pivot_frequency_slope(band.centre, magnitude, pivot, slope)))
+                                    .collect()
+                            };
+                            let strip_top = ANALYZER_CONTROLS_BOTTOM + 2.0;
+                            let strip_painter = ui.painter().with_clip_rect(plot_rect);
+                            for ((band, level_a), level_b) in bands.iter().zip(tilted_levels(masking_pair.0)).zip(tilted_levels(masking_pair.1)) {
+                                let score = collision_score(level_a, level_b, masking_threshold, masking_range);
+                                if score <= 0.0 {
+                                    continue;
+                                }
+                                let left = band.low.log10() * freq_scaler + x_shift + 1.0;
+                                let right = (band.high.log10() * freq_scaler + x_shift - 1.0).max(left + 1.0);
+                                let cell = Rect::from_min_max(Pos2::new(left, strip_top), Pos2::new(right, strip_top + 14.0));
+                                strip_painter.rect_filled(cell, Rounding::none(), Color32::RED.linear_multiply(0.2 + 0.6 * score));
+                                // Percentages only where they fit
+                                if cell.width() >= 28.0 {
+                                    strip_painter.text(cell.center(), Align2::CENTER_CENTER, format!("{:.0}%", score * 100.0), FontId::monospace(10.0), Color32::WHITE);
+                                }
+                            }
+                        }
+                        // Dragging pans, the wheel zooms frequency around the pointer (dB with Shift) and a double click resets
+                        let zoom_response = ui.interact(
+                            Rect::from_min_max(Pos2::new(0.0, ANALYZER_CONTROLS_BOTTOM), Pos2::new(ui.clip_rect().right(), ANALYZER_BOTTOM)),
+                            ui.id().with("analyzer_zoom"),
+                            egui::Sense::click_and_drag()
+                        );
+                        let range_params = [&params.analyzer_freq_min, &params.analyzer_freq_max, &params.analyzer_db_min, &params.analyzer_db_max];
+                        let decade_limits = (ANALYZER_FREQ_LIMITS.0.log10(), ANALYZER_FREQ_LIMITS.1.log10());
+                        if zoom_response.double_clicked() {
+                            for param in range_params {
+                                setter.begin_set_parameter(param);
+                                setter.set_parameter(param, param.default_plain_value());
+                                setter.end_set_parameter(param);
+                            }
+                        } else {
+                            if zoom_response.drag_started() {
+                                for param in range_params {
+                                    setter.begin_set_parameter(param);
+                                }
+                            }
+                            if zoom_response.dragged() {
+                                let delta = zoom_response.drag_delta();
+                                let decade_shift = delta.x / freq_scaler;
+                                let db_shift = delta.y / db_scaler;
+                                let (low, high) = clamp_span(decade_min - decade_shift, decade_max - decade_shift, decade_limits, 0.1);
+                                setter.set_parameter(&params.analyzer_freq_min, 10.0f32.powf(low));
+                                setter.set_parameter(&params.analyzer_freq_max, 10.0f32.powf(high));
+                                let (low, high) = clamp_span(db_min + db_shift, db_max + db_shift, ANALYZER_DB_LIMITS, 6.0);
+                                setter.set_parameter(&params.analyzer_db_min, low);
+                                setter.set_parameter(&params.analyzer_db_max, high);
+                            }
+                            if zoom_response.drag_released() {
+                                for param in range_params {
+                                    setter.end_set_parameter(param);
+                                }
+                            }
+                            let scroll_delta = ui.input().scroll_delta;
+                            let scroll = scroll_delta.x + scroll_delta.y;
+                            if let Some(pointer) = zoom_response.hover_pos().filter(|_| scroll != 0.0 && !zoom_response.dragged()) {
+                                // Scrolling up zooms in, keeping whatever is under the pointer in place
+                                let zoom = (-scroll * 0.002).exp();
+                                let (min_param, max_param, (low, high)) = if ui.input().modifiers.shift {
+                                    let anchor = (y_shift - pointer.y) / db_scaler;
+                                    let (low, high) = clamp_span(anchor - (anchor - db_min) * zoom, anchor + (db_max - anchor) * zoom, ANALYZER_DB_LIMITS, 6.0);
+                                    (&params.analyzer_db_min, &params.analyzer_db_max, (low, high))
+                                } else {
+                                    let anchor = (pointer.x - x_shift) / freq_scaler;
+                                    let (low, high) = clamp_span(anchor - (anchor - decade_min) * zoom, anchor + (decade_max - anchor) * zoom, decade_limits, 0.1);
+                                    (&params.analyzer_freq_min, &params.analyzer_freq_max, (10.0f32.powf(low), 10.0f32.powf(high)))
+                                };
+                                for (param, value) in [(min_param, low), (max_param, high)] {
+                                    setter.begin_set_parameter(param);
+                                    setter.set_parameter(param, value);
+                                    setter.end_set_parameter(param);
+                                }
+                            }
+                        }
+                        // Crosshair readout, the line view and the octave bars map x straight to frequency
+                        let hover = zoom_response.hover_pos().filter(|_| (!bar_mode || octave_layout.is_some()) && !zoom_response.dragged());
+                        if let Some(pointer) = hover {
+                            let freq = 10.0f32.powf((pointer.x - x_shift) / freq_scaler);
+                            let cursor_db = (y_shift - pointer.y) / db_scaler;
+                            let plot_painter = ui.painter().with_clip_rect(plot_rect);
+                            plot_painter.vline(pointer.x, ANALYZER_TOP..=ANALYZER_BOTTOM, Stroke::new(0.5, guidelines));
+                            plot_painter.hline(plot_rect.left()..=plot_rect.right(), pointer.y, Stroke::new(0.5, guidelines));
+
+                            let (note, cents) = note_name(freq);
+                            let mut readout = vec![(format!("{}  {} {:+.0}c  {:.1} dB", frequency_label(freq), note, cents, cursor_db), guidelines)];
+                            // Levels are read off the drawn curve or bar so they line up with the grid
+                            let bin = freq * fft_len as f32 / t_sr;
+                            let band = octave_layout
+                                .as_ref()
+                                .and_then(|bands| bands.iter().find(|band| band.low <= freq && freq < band.high));
+                            if let Some(band) = band {
+                                readout.push((format!("Band  {}", frequency_label(band.nominal())), guidelines));
+                            }
+                            for (((channel_magnitudes, enabled), name), color) in analyzer.magnitudes.iter()
+                                .zip(params.input_channels())
+                                .zip(INPUT_NAMES)
+                                .zip(channel_colors)
+                            {
+                                if enabled.value() && !channel_magnitudes.is_empty() {
+                                    let tilted = match band {
+                                        Some(band) => {
+                                            let band_level = band_levels(channel_magnitudes, bin_hz, std::slice::from_ref(band), noise_bandwidth)[0];
+                                            pivot_frequency_slope(band.centre, band_level, pivot, slope)
+                                        }
+                                        None => pivot_frequency_slope(freq, magnitude_at(channel_magnitudes, bin), pivot, slope),
+                                    };
+                                    let level = util::gain_to_db(tilted);
+                                    readout.push((format!("{:<6}{:.1} dB", name, level), color));
+                                }
+                            }
+                            for reference in params.references.read().unwrap().iter().filter(|reference| reference.visible) {
+                                if let Some(level) = reference.level_at(freq) {
+                                    let tilted = util::gain_to_db(pivot_frequency_slope(freq, util::db_to_gain(level), pivot, slope));
+                                    let [r, g, b] = reference.color;
+                                    readout.push((format!("{}  {:.1} dB", reference.name, tilted), Color32::from_rgb(r, g, b)));
+                                }
+                            }
+                            draw_readout(ui.painter(), pointer, plot_rect, readout, background_color);
+                        }
+                    } else if params.show_analyzer.value() && params.analyzer_view.value() == AnalyzerView::Transfer {
+                        let t_sr = sr.load(Ordering::Relaxed);
+                        let fft_len = analyzer.update([&samples, &samples_2], &params, &fft, t_sr);
+                        let bin_hz = t_sr / fft_len as f32;
+                        let response = analyzer.transfer.response();
+                        // Gain is centred on 0 dB with the gain range either side, phase runs +/-180 degrees and
+                        // coherence 0 to 1 over the same height
+                        let plot_rect = Rect::from_min_max(Pos2::new(0.0, ANALYZER_CONTROLS_BOTTOM), Pos2::new(ui.clip_rect().right(), ANALYZER_BOTTOM));
+                        let range = params.transfer_range.value();
+                        let middle = plot_rect.center().y;
+                        let half_height = plot_rect.height() / 2.0;
+                        let (decade_min, decade_max) = clamp_span(
+                            params.analyzer_freq_min.value().log10(),
+                            params.analyzer_freq_max.value().log10(),
+                            (ANALYZER_FREQ_LIMITS.0.log10(), ANALYZER_FREQ_LIMITS.1.log10()),
+                            0.1
+                        );
+                        let freq_scaler: f32 = (ANALYZER_RIGHT - ANALYZER_LEFT) / (decade_max - decade_min);
+                        let x_shift: f32 = ANALYZER_LEFT - decade_min * freq_scaler;
+                        let first_bin = ((10.0f32.powf(decade_min) / bin_hz) as usize).max(1);
+                        let last_bin = ((10.0f32.powf(decade_max) / bin_hz).ceil() as usize).min(response.len().saturating_sub(1));
+
+                        let mut shapes: Vec<egui::Shape> = vec![];
+                        if en_guidelines.load(Ordering::Relaxed) {
+                            frequency_guidelines(ui.painter(), &mut shapes, freq_scaler, x_shift);
+                            let db_step: f32 = [1.0, 2.0, 3.0, 6.0, 12.0]
+                                .into_iter()
+                                .find(|step| range / step <= 6.0)
+                                .unwrap_or(12.0);
+                            let mut db = (range / db_step).floor() * db_step;
+                            while db >= -range {
+                                let y = middle - db / range * half_height;
+                                shapes.push(
+                                    epaint::Shape::line_segment(
+                                        [Pos2::new(0.0, y), Pos2::new(plot_rect.right(), y)],
+                                        Stroke::new(0.5, if db == 0.0 { Color32::GRAY } else { Color32::DARK_GRAY })
+                                    )
+                                );
+                                ui.painter().text(
+                                    Pos2::new(plot_rect.right() - 4.0, y - 2.0),
+                                    Align2::RIGHT_BOTTOM,
+                                    format!("{:+} dB", db),
+                                    FontId::monospace(12.0),
+                                    Color32::GRAY
+                                );
+                                db -= db_step;
+                            }
+                        }
+                        // One line per run of bins the reference can measure, phase also breaks where it wraps
+                        let trace = |y_at: &dyn Fn(&TransferPoint) -> f32, max_jump: f32| {
+                            trace_runs(
+                                (first_bin..=last_bin).map(|bin| {
+                                    response[bin].map(|point| pos2((bin as f32 * bin_hz).log10() * freq_scaler + x_shift, y_at(&point)))
+                                }),
+                                max_jump
+                            )
+                        };
+                        let traces = [
+                            (trace(&|point| plot_rect.bottom() - point.coherence * plot_rect.height(), f32::INFINITY), Stroke::new(1.0, guidelines.linear_multiply(0.5))),
+                            (trace(&|point| middle - point.phase / std::f32::consts::PI * half_height, half_height), Stroke::new(1.0, final_aux_line_color)),
+                            (trace(&|point| middle - util::gain_to_db(point.gain) / range * half_height, f32::INFINITY), Stroke::new(1.5, final_primary_color)),
+                        ];
+                        for (runs, stroke) in traces {
+                            for run in runs {
+                                shapes.push(epaint::Shape::line(run, stroke));
+                            }
+                        }
+                        ui.painter().with_clip_rect(plot_rect).extend(shapes);
+
+                        // Legend, and the delay through the chain from the group delay where the measurement holds up
+                        let mut legend_pos = Pos2::new(ANALYZER_LEFT + 4.0, plot_rect.top() + 4.0);
+                        for (text, color) in [("Gain", final_primary_color), ("Phase +/-180", final_aux_line_color), ("Coherence 0-1", guidelines)] {
+                            let rect = ui.painter().text(legend_pos, Align2::LEFT_TOP, text, FontId::monospace(12.0), color);
+                            legend_pos.x = rect.right() + 12.0;
+                        }
+                        let mut delays: Vec<f32> = (first_bin..=last_bin)
+                            .filter(|bin| response[*bin].is_some_and(|point| point.coherence > 0.9))
+                            .filter_map(|bin| group_delay(&response, bin, bin_hz))
+                            .collect();
+                        delays.sort_by(f32::total_cmp);
+                        let delay_text = match delays.get(delays.len() / 2) {
+                            Some(delay) => format!("Delay {:.2} ms ({:.0} samples)", delay * 1000.0, delay * t_sr),
+                            None => String::from("No coherent signal, route the dry signal to the reference input"),
+                        };
+                        ui.painter().text(legend_pos, Align2::LEFT_TOP, delay_text, FontId::monospace(12.0), guidelines);
+
+                        // Readout of the response under the pointer
+                        let hover_response = ui.interact(plot_rect, ui.id().with("transfer_hover"), egui::Sense::hover());
+                        if let Some(pointer) = hover_response.hover_pos() {
+                            let freq = 10.0f32.powf((pointer.x - x_shift) / freq_scaler);
+                            let bin = ((freq / bin_hz).round() as usize).min(response.len().saturating_sub(1));
+                            let (note, cents) = note_name(freq);
+                            let mut readout = vec![(format!("{}  {} {:+.0}c", frequency_label(freq), note, cents), guidelines)];
+                            match response.get(bin).copied().flatten() {
+                                Some(point) => {
+                                    readout.push((format!("Gain      {:+.1} dB", util::gain_to_db(point.gain)), final_primary_color));
+                                    readout.push((format!("Phase     {:+.0} deg", point.phase.to_degrees()), final_aux_line_color));
+                                    readout.push((format!("Coherence {:.2}", point.coherence), guidelines));
+                                    if let Some(delay) = group_delay(&response, bin, bin_hz) {
+                                        readout.push((format!("Delay     {:.2} ms", delay * 1000.0), guidelines));
+                                    }
+                                }
+                                None => readout.push((String::from("Reference too quiet to measure"), guidelines)),
+                            }
+                            let plot_painter = ui.painter().with_clip_rect(plot_rect);
+                            plot_painter.vline(pointer.x, plot_rect.top()..=plot_rect.bottom(), Stroke::new(0.5, guidelines));
+                            plot_painter.hline(plot_rect.left()..=plot_rect.right(), pointer.y, Stroke::new(0.5, guidelines));
+                            draw_readout(ui.painter(), pointer, plot_rect, readout, background_color);
+                        }
+                    } else if params.show_analyzer.value() && params.analyzer_view.value() == AnalyzerView::Phase {
+                        let t_sr = sr.load(Ordering::Relaxed);
+                        let fft_len = analyzer.update([&samples, &samples_2], &params, &fft, t_sr);
+                        let bin_hz = t_sr / fft_len as f32;
+                        let plot_rect = Rect::from_min_max(Pos2::new(0.0, ANALYZER_CONTROLS_BOTTOM), Pos2::new(ui.clip_rect().right(), ANALYZER_BOTTOM));
+                        let middle = plot_rect.center().y;
+                        let half_height = plot_rect.height() / 2.0;
+                        let (decade_min, decade_max) = clamp_span(
+                            params.analyzer_freq_min.value().log10(),
+                            params.analyzer_freq_max.value().log10(),
+                            (ANALYZER_FREQ_LIMITS.0.log10(), ANALYZER_FREQ_LIMITS.1.log10()),
+                            0.1
+                        );
+                        let freq_scaler: f32 = (ANALYZER_RIGHT - ANALYZER_LEFT) / (decade_max - decade_min);
+                        let x_shift: f32 = ANALYZER_LEFT - decade_min * freq_scaler;
+                        let first_bin = ((10.0f32.powf(decade_min) / bin_hz) as usize).max(1);
+                        let last_bin = (10.0f32.powf(decade_max) / bin_hz).ceil() as usize;
+                        let channel_colors = [final_primary_color, final_aux_line_color, final_aux_line_color_2, final_aux_line_color_3, final_aux_line_color_4, final_aux_line_color_5];
+                        let difference = params.phase_difference.value();
+                        let reference = params.transfer_reference.value().to_index();
+
+                        // Phase per bin of each trace with its name and color. Bins too quiet, or for the difference
+                        // too incoherent, to have a meaningful phase are None
+                        let mut traces: Vec<(String, Color32, Vec<Option<f32>>)> = if difference {
+                            let response = analyzer.transfer.response();
+                            vec![(
+                                format!("Main - {}", INPUT_NAMES[reference]),
+                                final_primary_color,
+                                response.iter().map(|point| point.filter(|point| point.coherence >= PHASE_COHERENCE).map(|point| point.phase)).collect()
+                            )]
+                        } else {
+                            let db_min = params.analyzer_db_min.value();
+                            let focused = params.focused_line.value().to_index();
+                            (0..ANALYZER_INPUTS)
+                                .rev()
+                                .filter(|channel| *channel != focused)
+                                .chain([focused])
+                                .filter(|channel| params.input_channels()[*channel].value())
+                                .map(|channel| {
+                                    let phases = analyzer.phases[channel]
+                                        .iter()
+                                        .zip(&analyzer.magnitudes[channel])
+                                        .map(|(phase, magnitude)| (util::gain_to_db(*magnitude) > db_min).then_some(*phase))
+                                        .collect();
+                                    (String::from(INPUT_NAMES[channel]), channel_colors[channel], phases)
+                                })
+                                .collect()
+                        };
+                        let unwrap = params.phase_unwrap.value();
+                        if unwrap {
+                            for (_, _, phases) in traces.iter_mut() {
+                                *phases = unwrap_phase(phases);
+                            }
+                        }
+                        // Wrapped phase runs +/-180 degrees, unwrapped the range grows in half turns to fit what's shown
+                        let half_turns = if unwrap {
+                            traces
+                                .iter()
+                                .flat_map(|(_, _, phases)| phases.iter().take(last_bin + 1).skip(first_bin).flatten())
+                                .fold(1.0f32, |widest, phase| widest.max((phase.abs() / std::f32::consts::PI).ceil()))
+                        } else {
+                            1.0
+                        };
+                        let phase_y = |phase: f32| middle - phase / (half_turns * std::f32::consts::PI) * half_height;
+
+                        let mut shapes: Vec<egui::Shape> = vec![];
+                        if en_guidelines.load(Ordering::Relaxed) {
+                            frequency_guidelines(ui.painter(), &mut shapes, freq_scaler, x_shift);
+                            let degree_range = half_turns * 180.0;
+                            let degree_step: f32 = [45.0, 90.0, 180.0, 360.0, 720.0, 1440.0, 2880.0]
+                                .into_iter()
+                                .find(|step| degree_range / step <= 4.0)
+                                .unwrap_or(degree_range / 4.0);
+                            let mut degrees = (degree_range / degree_step).floor() * degree_step;
+                            while degrees >= -degree_range {
+                                let y = phase_y(degrees.to_radians());
+                                shapes.push(
+                                    epaint::Shape::line_segment(
+                                        [Pos2::new(0.0, y), Pos2::new(plot_rect.right(), y)],
+                                        Stroke::new(0.5, if degrees == 0.0 { Color32::GRAY } else { Color32::DARK_GRAY })
+                                    )
+                                );
+                                ui.painter().text(
+                                    Pos2::new(plot_rect.right() - 4.0, y - 2.0),
+                                    Align2::RIGHT_BOTTOM,
+                                    format!("{:+} deg", degrees),
+                                    FontId::monospace(12.0),
+                                    Color32::GRAY
+                                );
+                                degrees -= degree_step;
+                            }
+                        }
+                        // Wrapped lines break where they wrap instead of drawing a line across the plot
+                        let max_jump = if unwrap { f32::INFINITY } else { half_height };
+                        for (_, color, phases) in traces.iter() {
+                            let points = (first_bin..=last_bin.min(phases.len().saturating_sub(1))).map(|bin| {
+                                phases[bin].map(|phase| pos2((bin as f32 * bin_hz).log10() * freq_scaler + x_shift, phase_y(phase)))
+                            });
+                            for run in trace_runs(points, max_jump) {
+                                shapes.push(epaint::Shape::line(run, Stroke::new(1.0, *color)));
+                            }
+                        }
+                        ui.painter().with_clip_rect(plot_rect).extend(shapes);
+
+                        if difference {
+                            let note = if traces.iter().all(|(_, _, phases)| phases.iter().all(Option::is_none)) {
+                                "No coherent signal between the two inputs"
+                            } else {
+                                "Shown where the inputs are coherent, 0 deg is in phase"
+                            };
+                            ui.painter().text(
+                                Pos2::new(ANALYZER_LEFT + 4.0, plot_rect.top() + 4.0),
+                                Align2::LEFT_TOP,
+                                format!("Main - {}  {}", INPUT_NAMES[reference], note),
+                                FontId::monospace(12.0),
+                                guidelines
+                            );
+                        }
+
+                        // Readout of each trace's phase under the pointer, the difference also as the time between the inputs
+                        let hover_response = ui.interact(plot_rect, ui.id().with("phase_hover"), egui::Sense::hover());
+                        if let Some(pointer) = hover_response.hover_pos() {
+                            let freq = 10.0f32.powf((pointer.x - x_shift) / freq_scaler);
+                            let bin = (freq / bin_hz).round() as usize;
+                            let (note, cents) = note_name(freq);
+                            let mut readout = vec![(format!("{}  {} {:+.0}c", frequency_label(freq), note, cents), guidelines)];
+                            for (name, color, phases) in traces.iter().rev() {
+                                let line = match phases.get(bin).copied().flatten() {
+                                    Some(phase) if difference && bin > 0 => format!(
+                                        "{:<12} {:+.0} deg  {:+.3} ms",
+                                        name,
+                                        phase.to_degrees(),
+                                        phase / (std::f32::consts::TAU * bin as f32 * bin_hz) * 1000.0
+                                    ),
+                                    Some(phase) => format!("{:<12} {:+.0} deg", name, phase.to_degrees()),
+                                    None => format!("{:<12} -", name),
+                                };
+                                readout.push((line, *color));
+                            }
+                            let plot_painter = ui.painter().with_clip_rect(plot_rect);
+                            plot_painter.vline(pointer.x, plot_rect.top()..=plot_rect.bottom(), Stroke::new(0.5, guidelines));
+                            plot_painter.hline(plot_rect.left()..=plot_rect.right(), pointer.y, Stroke::new(0.5, guidelines));
+                            draw_readout(ui.painter(), pointer, plot_rect, readout, background_color);
+                        }
+                    } else if params.show_spectrogram.value() {
+                        let t_sr = sr.load(Ordering::Relaxed);
+                        let source = params.spectrogram_source.value().to_index();
+                        let fft_len: usize = params.fft_size.value().len().min(samples.capacity());
+                        let hop: usize = params.fft_overlap.value().hop(fft_len);
+                        let history = ((params.spectrogram_history.value() * t_sr) as u64).max(1);
+                        let write = stereo_write_position([&samples, &samples_2]);
+                        let frames = analyzer.due_frames(write, samples.capacity(), fft_len, hop);
+                        if !frames.is_empty() {
+                            let window: Vec<f32> = params.fft_window.value().coefficients(fft_len);
+                            for behind in frames {
+                                let end = samples.position_before(write, behind);
+                                let spectrum = magnitude_spectrum([&samples, &samples_2], source, params.channel_modes()[source].value(), fft_len, end, &window, &fft);
+                                let time = analyzer.clock().saturating_sub(behind as u64);
+                                // Beat lines process() marked during this frame's hop
+                                let marks = samples.get_complex_samples_with_length(6, hop, end);
+                                let beat = marks
+                                    .iter()
+                                    .rposition(|mark| mark.re > 0.0)
+                                    .map(|index| (time.saturating_sub((marks.len() - 1 - index) as u64), marks[index].re >= 1.0));
+                                let column = SpectrogramColumn {
+                                    time,
+                                    span: hop,
+                                    levels: log_levels(&spectrum, t_sr / fft_len as f32, SPECTROGRAM_ROWS),
+                                    beat,
+                                };
+                                analyzer.add_column(source, column, history);
+                            }
+                        }
+                        // Newest frame on the right, the analyzer's frequency range runs up the side in decades
+                        // and its dB range is spread over the colormap
+                        let (db_min, db_max) = clamp_span(params.analyzer_db_min.value(), params.analyzer_db_max.value(), ANALYZER_DB_LIMITS, 6.0);
+                        let (decade_min, decade_max) = clamp_span(
+                            params.analyzer_freq_min.value().log10(),
+                            params.analyzer_freq_max.value().log10(),
+                            (ANALYZER_FREQ_LIMITS.0.log10(), ANALYZER_FREQ_LIMITS.1.log10()),
+                            0.1
+                        );
+                        let plot_rect = Rect::from_min_max(Pos2::new(ANALYZER_LEFT, ANALYZER_CONTROLS_BOTTOM), Pos2::new(ANALYZER_RIGHT, ANALYZER_BOTTOM));
+                        let pivot: f32 = params.tilt_pivot.value();
+                        let slope: f32 = params.tilt_slope.value().db_per_octave();
+                        let now = analyzer.clock();
+                        let decade_at = |y: f32| decade_max - (y - plot_rect.top()) / plot_rect.height() * (decade_max - decade_min);
+                        let x_at = |time: u64| plot_rect.right() - now.saturating_sub(time) as f32 / history as f32 * plot_rect.width();
+                        let column_at = |x: f32| {
+                            let time = now.saturating_sub(((plot_rect.right() - x) / plot_rect.width() * history as f32) as u64);
+                            let index = analyzer.spectrogram.partition_point(|column| column.time < time);
+                            analyzer.spectrogram.get(index).filter(|column| column.time.saturating_sub(column.span as u64) <= time)
+                        };
+                        // Tilted the same as the analyzer so the two read alike
+                        let tilt_at = |decade: f32| util::gain_to_db(pivot_frequency_slope(10.0f32.powf(decade), 1.0, pivot, slope));
+                        let (row_low, row_high) = (ANALYZER_FREQ_LIMITS.0.log10(), ANALYZER_FREQ_LIMITS.1.log10());
+                        let row_at = |decade: f32| (((decade - row_low) / (row_high - row_low) * SPECTROGRAM_ROWS as f32) as usize).min(SPECTROGRAM_ROWS - 1);
+
+                        // Drawn a pixel at a time into a texture, far fewer shapes than a rect per bin
+                        let (width, height) = (plot_rect.width() as usize, plot_rect.height() as usize);
+                        let rows: Vec<(usize, f32)> = (0..height)
+                            .map(|y| {
+                                let decade = decade_at(plot_rect.top() + y as f32 + 0.5);
+                                (row_at(decade), tilt_at(decade))
+                            })
+                            .collect();
+                        let palette = colormap_palette(params.spectrogram_colormap.value(), 256);
+                        let mut image = ColorImage::new([width, height], background_color);
+                        for x in 0..width {
+                            let Some(column) = column_at(plot_rect.left() + x as f32 + 0.5) else {
+                                continue;
+                            };
+                            for (y, (row, tilt)) in rows.iter().enumerate() {
+                                let level = ((column.levels[*row] + tilt - db_min) / (db_max - db_min)).clamp(0.0, 1.0);
+                                image.pixels[y * width + x] = palette[(level * (palette.len() - 1) as f32) as usize];
+                            }
+                        }
+                        match &mut analyzer.spectrogram_texture {
+                            Some(texture) => texture.set(image, TextureFilter::Nearest),
+                            None => analyzer.spectrogram_texture = Some(ui.ctx().load_texture("spectrogram", image, TextureFilter::Nearest)),
+                        }
+                        if let Some(texture) = &analyzer.spectrogram_texture {
+                            ui.painter().add(egui::Shape::image(
+                                texture.id(),
+                                plot_rect,
+                                Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0)),
+                                Color32::WHITE
+                            ));
+                        }
+
+                        let plot_painter = ui.painter().with_clip_rect(plot_rect);
+                        if en_guidelines.load(Ordering::Relaxed) {
+                            let freqs: [f32; 10] = [20.0, 50.0, 100.0, 200.0, 500.0, 1000.0, 2000.0, 5000.0, 10000.0, 18000.0];
+                            for freq in freqs {
+                                let y = plot_rect.top() + (decade_max - freq.log10()) / (decade_max - decade_min) * plot_rect.height();
+                                plot_painter.hline(plot_rect.left()..=plot_rect.right(), y, Stroke::new(0.5, guidelines.linear_multiply(0.5)));
+                                plot_painter.text(
+                                    Pos2::new(plot_rect.left() + 2.0, y - 2.0),
+                                    Align2::LEFT_BOTTOM,
+                                    freq,
+                                    FontId::monospace(12.0),
+                                    guidelines
+                                );
+                            }
+                        }
+                        // Bar lines stand out over the beats
+                        for (time, bar) in analyzer.spectrogram.iter().filter_map(|column| column.beat) {
+                            let stroke = if bar { Stroke::new(1.0, guidelines) } else { Stroke::new(0.5, guidelines.linear_multiply(0.5)) };
+                            plot_painter.vline(x_at(time), plot_rect.top()..=plot_rect.bottom(), stroke);
+                        }
+
+                        // Readout of where the pointer is in time and frequency and the level there
+                        let hover_response = ui.interact(plot_rect, ui.id().with("spectrogram_hover"), egui::Sense::hover());
+                        if let Some(pointer) = hover_response.hover_pos() {
+                            let decade = decade_at(pointer.y);
+                            let freq = 10.0f32.powf(decade);
+                            let (note, cents) = note_name(freq);
+                            let seconds = (plot_rect.right() - pointer.x) / plot_rect.width() * params.spectrogram_history.value();
+                            let mut readout = format!("{}  {} {:+.0}c  -{:.2} s", frequency_label(freq), note, cents, seconds);
+                            if let Some(column) = column_at(pointer.x) {
+                                readout += &format!("  {:.1} dB", column.levels[row_at(decade)] + tilt_at(decade));
+                            }
+                            plot_painter.vline(pointer.x, plot_rect.top()..=plot_rect.bottom(), Stroke::new(0.5, guidelines));
+                            plot_painter.hline(plot_rect.left()..=plot_rect.right(), pointer.y, Stroke::new(0.5, guidelines));
+                            draw_readout(ui.painter(), pointer, plot_rect, vec![(readout, guidelines)], background_color);
                         }
                     } else {
                         //let internal_length = samples.internal_length.load(Ordering::SeqCst);
                         //let internal_length_2 = samples_2.internal_length.load(Ordering::SeqCst);
                         //let write_indices = samples.write_indices[0].load(Ordering::SeqCst);
-                        let sbl: PlotPoints = {
+                        // The ring can't hold the long view's window so it's drawn from the envelope mipmap
+                        let long_view = params.long_view.value();
+                        let long_window = (sample_rate.load(Ordering::Relaxed) * params.display_ms() / 1000.0) as usize;
+                        let sbl: PlotPoints = if long_view {
+                            long_points(&envelope_store, 6, long_window, params.flip.value(), true, 0.0)
+                        } else {
                             // Get a read lock on the buffer
                             let buffer_len = samples.internal_length.load(Ordering::Acquire);
                             let main_samples = samples.get_samples(6); // Now returns Vec<f32> directly
 
                             (0..buffer_len)
                                 .map(|i| {
-                                    let x = if dir_var.load(Ordering::Relaxed) { (buffer_len - i) as f64 } else { i as f64 }; // Linear index
+                                    let x = if params.flip.value() { (buffer_len - i) as f64 } else { i as f64 }; // Linear index
                                     let y = main_samples[i] as f64;
                                     [x, y]
                                 })
@@ -1957,303 +2595,110 @@ Version 1.4.3");
                             .color(guidelines)
                             .stroke(Stroke::new(0.25, guidelines.linear_multiply(0.5)));
                         let offset_osc_view;
-                        if stereo_view.load(Ordering::Relaxed) {
+                        if params.stereo_view.value() {
                             offset_osc_view = 1.0;
                         } else {
                             offset_osc_view = 0.0;
                         }
+                        let flip = params.flip.value();
+                        let decimation = params.decimation.value();
+                        let envelope = decimation != Decimation::Skip;
+                        // With RMS on, the min/max band is softened so the RMS body reads on top of it
+                        let band = |color: Color32| if decimation == Decimation::MinMaxRms && !long_view { color.linear_multiply(0.35) } else { color };
+                        let channel_points = |buffer: &OptimizedBuffer, store: &EnvelopeStore, channel: usize, enabled: bool, offset: f64| {
+                            if long_view {
+                                long_points(store, channel, long_window, flip, enabled, offset)
+                            } else {
+                                scope_points(buffer, channel, envelope, flip, enabled, offset)
+                            }
+                        };
+                        let left_on = params.en_left_channel.value();
+                        let right_on = params.en_right_channel.value();
                         // CHANNEL 0
                         /////////////////////////////////////////////////////////////////////////////////////////
                         // Primary Input
-                        // Primary Input
-                        let data: PlotPoints = {
-                            // Get a read lock on the buffer
-                            let buffer_len = samples.internal_length.load(Ordering::Acquire);
-                            let main_samples = samples.get_samples(0); // Now returns Vec<f32> directly
-
-                            (0..buffer_len)
-                                .map(|i| {
-                                    let x = if dir_var.load(Ordering::Relaxed) { (buffer_len - i) as f64 } else { i as f64 }; // Linear index
-                                    let y = if en_main.load(Ordering::Relaxed) && en_left_channel.load(Ordering::Relaxed) {
-                                        main_samples[i] as f64 + offset_osc_view
-                                    } else {
-                                        0.0
-                                    };
-                                    [x, y]
-                                })
-                                .collect()
-                        };
-                        line = Line::new(data)
-                            //.color(primary_line_color)
-                            .stroke(Stroke::new(1.1, primary_line_color));
+                        line = Line::new(channel_points(&samples, &envelope_store, 0, params.en_main.value() && left_on, offset_osc_view))
+                            .stroke(Stroke::new(1.1, band(primary_line_color)));
                         // Aux inputs
-                        let aux_data: PlotPoints = {
-                            let buffer_len = samples.internal_length.load(Ordering::Acquire);
-                            let aux1_samples = samples.get_samples(1); // Channel 1 for Aux1
-
-                            (0..buffer_len)
-                                .map(|i| {
-                                    let x = if dir_var.load(Ordering::Relaxed) { (buffer_len - i) as f64 } else { i as f64 };
-                                    let y = if en_aux1.load(Ordering::Relaxed) && en_left_channel.load(Ordering::Relaxed) {
-                                        aux1_samples[i] as f64 + offset_osc_view
-                                    } else {
-                                        0.0
-                                    };
-                                    [x, y]
-                                })
-                                .collect()
-                        };
-                        aux_line = Line::new(aux_data)
-                            //.color(user_aux_1)
-                            .stroke(Stroke::new(1.0, user_aux_1));
-                        let aux_data_2: PlotPoints = {
-                            let buffer_len = samples.internal_length.load(Ordering::Acquire);
-                            let aux2_samples = samples.get_samples(2); // Channel 2 for Aux2
-                                                
-                            (0..buffer_len)
-                                .map(|i| {
-                                    let x = if dir_var.load(Ordering::Relaxed) { (buffer_len - i) as f64 } else { i as f64 };
-                                    let y = if en_aux2.load(Ordering::Relaxed) && en_left_channel.load(Ordering::Relaxed) {
-                                        aux2_samples[i] as f64 + offset_osc_view
-                                    } else {
-                                        0.0
-                                    };
-                                    [x, y]
-                                })
-                                .collect()
-                        };
-                        aux_line_2 = Line::new(aux_data_2)
-                            //.color(user_aux_2)
-                            .stroke(Stroke::new(1.0, user_aux_2));
-                        let aux_data_3: PlotPoints = {
-                            let buffer_len = samples.internal_length.load(Ordering::Acquire);
-                            let aux3_samples = samples.get_samples(3); // Channel 3 for Aux3
-                                                
-                            (0..buffer_len)
-                                .map(|i| {
-                                    let x = if dir_var.load(Ordering::Relaxed) { (buffer_len - i) as f64 } else { i as f64 };
-                                    let y = if en_aux3.load(Ordering::Relaxed) && en_left_channel.load(Ordering::Relaxed) {
-                                        aux3_samples[i] as f64 + offset_osc_view
-                                    } else {
-                                        0.0
-                                    };
-                                    [x, y]
-                                })
-                                .collect()
-                        };
-                        aux_line_3 = Line::new(aux_data_3)
-                            //.color(user_aux_3)
-                            .stroke(Stroke::new(1.0, user_aux_3));
-                        let aux_data_4: PlotPoints = {
-                            let buffer_len = samples.internal_length.load(Ordering::Acquire);
-                            let aux4_samples = samples.get_samples(4); // Channel 4 for Aux4
-                                                
-                            (0..buffer_len)
-                                .map(|i| {
-                                    let x = if dir_var.load(Ordering::Relaxed) { (buffer_len - i) as f64 } else { i as f64 };
-                                    let y = if en_aux4.load(Ordering::Relaxed) && en_left_channel.load(Ordering::Relaxed) {
-                                        aux4_samples[i] as f64 + offset_osc_view
-                                    } else {
-                                        0.0
-                                    };
-                                    [x, y]
-                                })
-                                .collect()
-                        };
-                        aux_line_4 = Line::new(aux_data_4)
-                            //.color(user_aux_4)
-                            .stroke(Stroke::new(1.0, user_aux_4));
-                        let aux_data_5: PlotPoints = {
-                            let buffer_len = samples.internal_length.load(Ordering::Acquire);
-                            let aux5_samples = samples.get_samples(5); // Channel 5 for Aux5
-                                                
-                            (0..buffer_len)
-                                .map(|i| {
-                                    let x = if dir_var.load(Ordering::Relaxed) { (buffer_len - i) as f64 } else { i as f64 };
-                                    let y = if en_aux5.load(Ordering::Relaxed) && en_left_channel.load(Ordering::Relaxed) {
-                                        aux5_samples[i] as f64 + offset_osc_view
-                                    } else {
-                                        0.0
-                                    };
-                                    [x, y]
-                                })
-                                .collect()
-                        };
-                        aux_line_5 = Line::new(aux_data_5)
-                            //.color(user_aux_5)
-                            .stroke(Stroke::new(1.0, user_aux_5));
-                        let sum_plotpoints: PlotPoints = {
-                            let buffer_len = samples.internal_length.load(Ordering::Acquire);
-                            let sum_samples = samples.get_samples(7); // Channel 7 for sum
-                                                
-                            (0..buffer_len)
-                                .map(|i| {
-                                    let x = if dir_var.load(Ordering::Relaxed) { (buffer_len - i) as f64 } else { i as f64 };
-                                    let y = if en_sum.load(Ordering::Relaxed) && en_left_channel.load(Ordering::Relaxed) {
-                                        sum_samples[i] as f64 + offset_osc_view
-                                    } else {
-                                        0.0
-                                    };
-                                    [x, y]
-                                })
-                                .collect()
-                        };
-                        sum_line = Line::new(sum_plotpoints)
-                                //.color(user_sum_line.linear_multiply(0.25))
-                                .stroke(Stroke::new(0.9, user_sum_line));
+                        aux_line = Line::new(channel_points(&samples, &envelope_store, 1, params.en_aux1.value() && left_on, offset_osc_view))
+                            .stroke(Stroke::new(1.0, band(user_aux_1)));
+                        aux_line_2 = Line::new(channel_points(&samples, &envelope_store, 2, params.en_aux2.value() && left_on, offset_osc_view))
+                            .stroke(Stroke::new(1.0, band(user_aux_2)));
+                        aux_line_3 = Line::new(channel_points(&samples, &envelope_store, 3, params.en_aux3.value() && left_on, offset_osc_view))
+                            .stroke(Stroke::new(1.0, band(user_aux_3)));
+                        aux_line_4 = Line::new(channel_points(&samples, &envelope_store, 4, params.en_aux4.value() && left_on, offset_osc_view))
+                            .stroke(Stroke::new(1.0, band(user_aux_4)));
+                        aux_line_5 = Line::new(channel_points(&samples, &envelope_store, 5, params.en_aux5.value() && left_on, offset_osc_view))
+                            .stroke(Stroke::new(1.0, band(user_aux_5)));
+                        sum_line = Line::new(channel_points(&samples, &envelope_store, 7, params.en_sum.value() && left_on, offset_osc_view))
+                                .stroke(Stroke::new(0.9, band(user_sum_line)));
 
                         // CHANNEL 1
                         /////////////////////////////////////////////////////////////////////////////////////////
                         // Primary Input
-                        let data_2: PlotPoints = {
-                            // Get a read lock on the buffer
-                            let buffer_len = samples_2.internal_length.load(Ordering::Acquire);
-                            let main_samples = samples_2.get_samples(0); // Now returns Vec<f32> directly
-
-                            (0..buffer_len)
-                                .map(|i| {
-                                    let x = if dir_var.load(Ordering::Relaxed) { (buffer_len - i) as f64 } else { i as f64 }; // Linear index
-                                    let y = if en_main.load(Ordering::Relaxed) && en_right_channel.load(Ordering::Relaxed) {
-                                        main_samples[i] as f64 - offset_osc_view
-                                    } else {
-                                        0.0
-                                    };
-                                    [x, y]
-                                })
-                                .collect()
-                        };
-                        line_2 = Line::new(data_2)
-                            //.color(primary_line_color)
-                            .stroke(Stroke::new(1.1, primary_line_color));
+                        line_2 = Line::new(channel_points(&samples_2, &envelope_store_2, 0, params.en_main.value() && right_on, -offset_osc_view))
+                            .stroke(Stroke::new(1.1, band(primary_line_color)));
                         // Aux inputs
-                        #[allow(non_snake_case)]
-                        let aux_data__2: PlotPoints = {
-                            let buffer_len = samples_2.internal_length.load(Ordering::Acquire);
-                            let aux1_samples = samples_2.get_samples(1); // Channel 1 for Aux1
+                        aux_line__2 = Line::new(channel_points(&samples_2, &envelope_store_2, 1, params.en_aux1.value() && right_on, -offset_osc_view))
+                            .stroke(Stroke::new(1.0, band(user_aux_1)));
+                        aux_line_2_2 = Line::new(channel_points(&samples_2, &envelope_store_2, 2, params.en_aux2.value() && right_on, -offset_osc_view))
+                            .stroke(Stroke::new(1.0, band(user_aux_2)));
+                        aux_line_3_2 = Line::new(channel_points(&samples_2, &envelope_store_2, 3, params.en_aux3.value() && right_on, -offset_osc_view))
+                            .stroke(Stroke::new(1.0, band(user_aux_3)));
+                        aux_line_4_2 = Line::new(channel_points(&samples_2, &envelope_store_2, 4, params.en_aux4.value() && right_on, -offset_osc_view))
+                            .stroke(Stroke::new(1.0, band(user_aux_4)));
+                        aux_line_5_2 = Line::new(channel_points(&samples_2, &envelope_store_2, 5, params.en_aux5.value() && right_on, -offset_osc_view))
+                            .stroke(Stroke::new(1.0, band(user_aux_5)));
+                        let sum_line_2 = Line::new(channel_points(&samples_2, &envelope_store_2, 7, params.en_sum.value() && right_on, -offset_osc_view))
+                                .stroke(Stroke::new(0.9, band(user_sum_line)));
 
-                            (0..buffer_len)
-                                .map(|i| {
-                                    let x = if dir_var.load(Ordering::Relaxed) { (buffer_len - i) as f64 } else { i as f64 };
-                                    let y = if en_aux1.load(Ordering::Relaxed) && en_right_channel.load(Ordering::Relaxed) {
-                                        aux1_samples[i] as f64 - offset_osc_view
-                                    } else {
-                                        0.0
-                                    };
-                                    [x, y]
-                                })
-                                .collect()
-                        };
-                        aux_line__2 = Line::new(aux_data__2)
-                            //.color(user_aux_1)
-                            .stroke(Stroke::new(1.0, user_aux_1));
-                        let aux_data_2_2: PlotPoints = {
-                            let buffer_len = samples_2.internal_length.load(Ordering::Acquire);
-                            let aux2_samples = samples_2.get_samples(2); // Channel 2 for Aux2
-                                                
-                            (0..buffer_len)
-                                .map(|i| {
-                                    let x = if dir_var.load(Ordering::Relaxed) { (buffer_len - i) as f64 } else { i as f64 };
-                                    let y = if en_aux2.load(Ordering::Relaxed) && en_right_channel.load(Ordering::Relaxed) {
-                                        aux2_samples[i] as f64 - offset_osc_view
-                                    } else {
-                                        0.0
-                                    };
-                                    [x, y]
-                                })
-                                .collect()
-                        };
-                        aux_line_2_2 = Line::new(aux_data_2_2)
-                            //.color(user_aux_2)
-                            .stroke(Stroke::new(1.0, user_aux_2));
-                        let aux_data_3_2: PlotPoints = {
-                            let buffer_len = samples_2.internal_length.load(Ordering::Acquire);
-                            let aux3_samples = samples_2.get_samples(3); // Channel 3 for Aux3
-                                                
-                            (0..buffer_len)
-                                .map(|i| {
-                                    let x = if dir_var.load(Ordering::Relaxed) { (buffer_len - i) as f64 } else { i as f64 };
-                                    let y = if en_aux3.load(Ordering::Relaxed) && en_right_channel.load(Ordering::Relaxed) {
-                                        aux3_samples[i] as f64 - offset_osc_view
-                                    } else {
-                                        0.0
-                                    };
-                                    [x, y]
-                                })
-                                .collect()
-                        };
-                        aux_line_3_2 = Line::new(aux_data_3_2)
-                            //.color(user_aux_3)
-                            .stroke(Stroke::new(1.0, user_aux_3));
-                        let aux_data_4_2: PlotPoints = {
-                            let buffer_len = samples_2.internal_length.load(Ordering::Acquire);
-                            let aux4_samples = samples_2.get_samples(4); // Channel 4 for Aux4
-                                                
-                            (0..buffer_len)
-                                .map(|i| {
-                                    let x = if dir_var.load(Ordering::Relaxed) { (buffer_len - i) as f64 } else { i as f64 };
-                                    let y = if en_aux4.load(Ordering::Relaxed) && en_right_channel.load(Ordering::Relaxed) {
-                                        aux4_samples[i] as f64 - offset_osc_view
-                                    } else {
-                                        0.0
-                                    };
-                                    [x, y]
-                                })
-                                .collect()
-                        };
-                        aux_line_4_2 = Line::new(aux_data_4_2)
-                            //.color(user_aux_4)
-                            .stroke(Stroke::new(1.0, user_aux_4));
-                        let aux_data_5_2: PlotPoints = {
-                            let buffer_len = samples_2.internal_length.load(Ordering::Acquire);
-                            let aux5_samples = samples_2.get_samples(5); // Channel 5 for Aux5
-                                                
-                            (0..buffer_len)
-                                .map(|i| {
-                                    let x = if dir_var.load(Ordering::Relaxed) { (buffer_len - i) as f64 } else { i as f64 };
-                                    let y = if en_aux5.load(Ordering::Relaxed) && en_right_channel.load(Ordering::Relaxed) {
-                                        aux5_samples[i] as f64 - offset_osc_view
-                                    } else {
-                                        0.0
-                                    };
-                                    [x, y]
-                                })
-                                .collect()
-                        };
-                        aux_line_5_2 = Line::new(aux_data_5_2)
-                            //.color(user_aux_5)
-                            .stroke(Stroke::new(1.0, user_aux_5));
-                        let sum_plotpoints_2: PlotPoints = {
-                            let buffer_len = samples_2.internal_length.load(Ordering::Acquire);
-                            let sum_samples = samples_2.get_samples(7); // Channel 7 for sum
-                                                
-                            (0..buffer_len)
-                                .map(|i| {
-                                    let x = if dir_var.load(Ordering::Relaxed) { (buffer_len - i) as f64 } else { i as f64 };
-                                    let y = if en_sum.load(Ordering::Relaxed) && en_right_channel.load(Ordering::Relaxed) {
-                                        sum_samples[i] as f64 - offset_osc_view
-                                    } else {
-                                        0.0
-                                    };
-                                    [x, y]
-                                })
-                                .collect()
+                        // RMS bodies drawn over the min/max bands, in channel order
+                        let mut rms_lines: Vec<Line> = Vec::new();
+                        if decimation == Decimation::MinMaxRms && !long_view {
+                            let channel_colors = [
+                                (0, &params.en_main, primary_line_color),
+                                (1, &params.en_aux1, user_aux_1),
+                                (2, &params.en_aux2, user_aux_2),
+                                (3, &params.en_aux3, user_aux_3),
+                                (4, &params.en_aux4, user_aux_4),
+                                (5, &params.en_aux5, user_aux_5),
+                                (7, &params.en_sum, user_sum_line),
+                            ];
+                            for (channel, enabled, color) in channel_colors {
+                                if !enabled.value() {
+                                    continue;
+                                }
+                                if left_on {
+                                    rms_lines.push(Line::new(rms_points(&samples, channel, flip, offset_osc_view)).stroke(Stroke::new(1.0, color)));
+                                }
+                                if right_on {
+                                    rms_lines.push(Line::new(rms_points(&samples_2, channel, flip, -offset_osc_view)).stroke(Stroke::new(1.0, color)));
+                                }
+                            }
+                        }
+                        let y_scale = if params.stereo_view.value() { 2.0 } else { 1.0 };
+                        // Sync writes in place so there's no history to scrub through, and the long view already shows it all
+                        // The trigger draws in place too, its level and position are marked on the scope
+                        let trigger_active = params.trigger_mode.value() != TriggerMode::Off && !long_view;
+                        let trigger_level = params.trigger_level.value() as f64 + offset_osc_view;
+                        let trigger_x = {
+                            let buffer_len = samples.internal_length.load(Ordering::Acquire);
+                            let pre_points = ((buffer_len as f32 * params.trigger_pre.value() / 100.0) as usize).min(samples.history_len());
+                            if flip { (buffer_len - pre_points) as f64 } else { pre_points as f64 }
                         };
-                        let sum_line_2 = Line::new(sum_plotpoints_2)
-                                //.color(user_sum_line.linear_multiply(0.25))
-                                .stroke(Stroke::new(0.9, user_sum_line));
-                        let y_scale = if stereo_view.load(Ordering::Relaxed) { 2.0 } else { 1.0 };
+                        let scrubbing = hold.load(Ordering::Relaxed) && !params.sync.value() && !long_view && !trigger_active;
                         // Show the Oscilloscope
-                        Plot::new("Oscilloscope")
+                        let scope_response = Plot::new("Oscilloscope")
                             .show_background(false)
                             .include_x(130.0)
                             .include_y(-y_scale)
                             .include_y(y_scale)
                             //.center_y_axis(true)
                             .allow_zoom(true)
-                            .allow_scroll(true)
+                            .allow_scroll(!scrubbing)
                             .height(480.0)
                             .width(1040.0)
-                            .allow_drag(true)
+                            .allow_drag(!scrubbing)
                             // Blank out the X axis labels
                             .x_axis_formatter(|_, _range: &RangeInclusive<f64>| String::new())
                             .x_grid_spacer(move |_| {
@@ -2266,9 +2711,21 @@ Version 1.4.3");
                             .label_formatter(|_, _| "".to_owned())
                             .show(ui, |plot_ui| {
                                 plot_ui.line(sbl_line);
-                                if en_sum.load(Ordering::Relaxed) {
+                                if trigger_active {
+                                    plot_ui.hline(
+                                        HLine::new(trigger_level)
+                                            .color(guidelines)
+                                            .style(LineStyle::dashed_loose()),
+                                    );
+                                    plot_ui.vline(
+                                        VLine::new(trigger_x)
+                                            .color(guidelines)
+                                            .style(LineStyle::dashed_loose()),
+                                    );
+                                }
+                                if params.en_sum.value() {
                                     // Draw the sum line first so it's furthest behind
-                                    if en_filled_osc.load(Ordering::Relaxed) {
+                                    if params.fill.value() {
                                         plot_ui.line(sum_line.fill(0.0));
                                         plot_ui.line(sum_line_2.fill(0.0));
                                     } else {
@@ -2278,11 +2735,11 @@ Version 1.4.3");
                                 }
                                 // Figure out the lines to draw
                                 // Get our fill for this sequence
-                                let fill = en_filled_osc.load(Ordering::Relaxed);
+                                let fill = params.fill.value();
                                 // Draw whichever order next
-                                match ontop.load(Ordering::Relaxed) {
+                                match params.focused_line.value().to_index() {
                                     0 => {
-                                        if en_aux5.load(Ordering::Relaxed) {
+                                        if params.en_aux5.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_5.fill(0.0));
                                                 plot_ui.line(aux_line_5_2.fill(0.0));
@@ -2291,7 +2748,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line_5_2);
                                             }
                                         }
-                                        if en_aux4.load(Ordering::Relaxed) {
+                                        if params.en_aux4.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_4.fill(0.0));
                                                 plot_ui.line(aux_line_4_2.fill(0.0));
@@ -2300,7 +2757,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line_4_2);
                                             }
                                         }
-                                        if en_aux3.load(Ordering::Relaxed) {
+                                        if params.en_aux3.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_3.fill(0.0));
                                                 plot_ui.line(aux_line_3_2.fill(0.0));
@@ -2309,7 +2766,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line_3_2);
                                             }
                                         }
-                                        if en_aux2.load(Ordering::Relaxed) {
+                                        if params.en_aux2.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_2.fill(0.0));
                                                 plot_ui.line(aux_line_2_2.fill(0.0));
@@ -2318,7 +2775,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line_2_2);
                                             }
                                         }
-                                        if en_aux1.load(Ordering::Relaxed) {
+                                        if params.en_aux1.value() {
                                             if fill {
                                                 plot_ui.line(aux_line.fill(0.0));
                                                 plot_ui.line(aux_line__2.fill(0.0));
@@ -2327,7 +2784,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line__2);
                                             }
                                         }
-                                        if en_main.load(Ordering::Relaxed) {
+                                        if params.en_main.value() {
                                             if fill {
                                                 plot_ui.line(line.fill(0.0));
                                                 plot_ui.line(line_2.fill(0.0));
@@ -2338,7 +2795,7 @@ Version 1.4.3");
                                         }
                                     }
                                     1 => {
-                                        if en_main.load(Ordering::Relaxed) {
+                                        if params.en_main.value() {
                                             if fill {
                                                 plot_ui.line(line.fill(0.0));
                                                 plot_ui.line(line_2.fill(0.0));
@@ -2347,7 +2804,7 @@ Version 1.4.3");
                                                 plot_ui.line(line_2);
                                             }
                                         }
-                                        if en_aux5.load(Ordering::Relaxed) {
+                                        if params.en_aux5.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_5.fill(0.0));
                                                 plot_ui.line(aux_line_5_2.fill(0.0));
@@ -2356,7 +2813,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line_5_2);
                                             }
                                         }
-                                        if en_aux4.load(Ordering::Relaxed) {
+                                        if params.en_aux4.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_4.fill(0.0));
                                                 plot_ui.line(aux_line_4_2.fill(0.0));
@@ -2365,7 +2822,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line_4_2);
                                             }
                                         }
-                                        if en_aux3.load(Ordering::Relaxed) {
+                                        if params.en_aux3.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_3.fill(0.0));
                                                 plot_ui.line(aux_line_3_2.fill(0.0));
@@ -2374,7 +2831,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line_3_2);
                                             }
                                         }
-                                        if en_aux2.load(Ordering::Relaxed) {
+                                        if params.en_aux2.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_2.fill(0.0));
                                                 plot_ui.line(aux_line_2_2.fill(0.0));
@@ -2383,7 +2840,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line_2_2);
                                             }
                                         }
-                                        if en_aux1.load(Ordering::Relaxed) {
+                                        if params.en_aux1.value() {
                                             if fill {
                                                 plot_ui.line(aux_line.fill(0.0));
                                                 plot_ui.line(aux_line__2.fill(0.0));
@@ -2394,7 +2851,7 @@ Version 1.4.3");
                                         }
                                     }
                                     2 => {
-                                        if en_aux1.load(Ordering::Relaxed) {
+                                        if params.en_aux1.value() {
                                             if fill {
                                                 plot_ui.line(aux_line.fill(0.0));
                                                 plot_ui.line(aux_line__2.fill(0.0));
@@ -2403,7 +2860,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line__2);
                                             }
                                         }
-                                        if en_main.load(Ordering::Relaxed) {
+                                        if params.en_main.value() {
                                             if fill {
                                                 plot_ui.line(line.fill(0.0));
                                                 plot_ui.line(line_2.fill(0.0));
@@ -2412,7 +2869,7 @@ Version 1.4.3");
                                                 plot_ui.line(line_2);
                                             }
                                         }
-                                        if en_aux5.load(Ordering::Relaxed) {
+                                        if params.en_aux5.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_5.fill(0.0));
                                                 plot_ui.line(aux_line_5_2.fill(0.0));
@@ -2421,7 +2878,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line_5_2);
                                             }
                                         }
-                                        if en_aux4.load(Ordering::Relaxed) {
+                                        if params.en_aux4.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_4.fill(0.0));
                                                 plot_ui.line(aux_line_4_2.fill(0.0));
@@ -2430,7 +2887,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line_4_2);
                                             }
                                         }
-                                        if en_aux3.load(Ordering::Relaxed) {
+                                        if params.en_aux3.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_3.fill(0.0));
                                                 plot_ui.line(aux_line_3_2.fill(0.0));
@@ -2439,7 +2896,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line_3_2);
                                             }
                                         }
-                                        if en_aux2.load(Ordering::Relaxed) {
+                                        if params.en_aux2.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_2.fill(0.0));
                                                 plot_ui.line(aux_line_2_2.fill(0.0));
@@ -2450,7 +2907,7 @@ Version 1.4.3");
                                         }
                                     }
                                     3 => {
-                                        if en_aux2.load(Ordering::Relaxed) {
+                                        if params.en_aux2.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_2.fill(0.0));
                                                 plot_ui.line(aux_line_2_2.fill(0.0));
@@ -2459,7 +2916,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line_2_2);
                                             }
                                         }
-                                        if en_aux1.load(Ordering::Relaxed) {
+                                        if params.en_aux1.value() {
                                             if fill {
                                                 plot_ui.line(aux_line.fill(0.0));
                                                 plot_ui.line(aux_line__2.fill(0.0));
@@ -2468,7 +2925,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line__2);
                                             }
                                         }
-                                        if en_main.load(Ordering::Relaxed) {
+                                        if params.en_main.value() {
                                             if fill {
                                                 plot_ui.line(line.fill(0.0));
                                                 plot_ui.line(line_2.fill(0.0));
@@ -2477,7 +2934,7 @@ Version 1.4.3");
                                                 plot_ui.line(line_2);
                                             }
                                         }
-                                        if en_aux5.load(Ordering::Relaxed) {
+                                        if params.en_aux5.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_5.fill(0.0));
                                                 plot_ui.line(aux_line_5_2.fill(0.0));
@@ -2486,7 +2943,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line_5_2);
                                             }
                                         }
-                                        if en_aux4.load(Ordering::Relaxed) {
+                                        if params.en_aux4.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_4.fill(0.0));
                                                 plot_ui.line(aux_line_4_2.fill(0.0));
@@ -2495,7 +2952,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line_4_2);
                                             }
                                         }
-                                        if en_aux3.load(Ordering::Relaxed) {
+                                        if params.en_aux3.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_3.fill(0.0));
                                                 plot_ui.line(aux_line_3_2.fill(0.0));
@@ -2506,7 +2963,7 @@ Version 1.4.3");
                                         }
                                     }
                                     4 => {
-                                        if en_aux3.load(Ordering::Relaxed) {
+                                        if params.en_aux3.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_3.fill(0.0));
                                                 plot_ui.line(aux_line_3_2.fill(0.0));
@@ -2515,7 +2972,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line_3_2);
                                             }
                                         }
-                                        if en_aux2.load(Ordering::Relaxed) {
+                                        if params.en_aux2.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_2.fill(0.0));
                                                 plot_ui.line(aux_line_2_2.fill(0.0));
@@ -2524,7 +2981,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line_2_2);
                                             }
                                         }
-                                        if en_aux1.load(Ordering::Relaxed) {
+                                        if params.en_aux1.value() {
                                             if fill {
                                                 plot_ui.line(aux_line.fill(0.0));
                                                 plot_ui.line(aux_line__2.fill(0.0));
@@ -2533,7 +2990,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line__2);
                                             }
                                         }
-                                        if en_main.load(Ordering::Relaxed) {
+                                        if params.en_main.value() {
                                             if fill {
                                                 plot_ui.line(line.fill(0.0));
                                                 plot_ui.line(line_2.fill(0.0));
@@ -2542,7 +2999,7 @@ Version 1.4.3");
                                                 plot_ui.line(line_2);
                                             }
                                         }
-                                        if en_aux5.load(Ordering::Relaxed) {
+                                        if params.en_aux5.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_5.fill(0.0));
                                                 plot_ui.line(aux_line_5_2.fill(0.0));
@@ -2551,7 +3008,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line_5_2);
                                             }
                                         }
-                                        if en_aux4.load(Ordering::Relaxed) {
+                                        if params.en_aux4.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_4.fill(0.0));
                                                 plot_ui.line(aux_line_4_2.fill(0.0));
@@ -2562,7 +3019,7 @@ Version 1.4.3");
                                         }
                                     }
                                     5 => {
-                                        if en_aux4.load(Ordering::Relaxed) {
+                                        if params.en_aux4.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_4.fill(0.0));
                                                 plot_ui.line(aux_line_4_2.fill(0.0));
@@ -2571,7 +3028,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line_4_2);
                                             }
                                         }
-                                        if en_aux3.load(Ordering::Relaxed) {
+                                        if params.en_aux3.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_3.fill(0.0));
                                                 plot_ui.line(aux_line_3_2.fill(0.0));
@@ -2580,7 +3037,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line_3_2);
                                             }
                                         }
-                                        if en_aux2.load(Ordering::Relaxed) {
+                                        if params.en_aux2.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_2.fill(0.0));
                                                 plot_ui.line(aux_line_2_2.fill(0.0));
@@ -2589,7 +3046,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line_2_2);
                                             }
                                         }
-                                        if en_aux1.load(Ordering::Relaxed) {
+                                        if params.en_aux1.value() {
                                             if fill {
                                                 plot_ui.line(aux_line.fill(0.0));
                                                 plot_ui.line(aux_line__2.fill(0.0));
@@ -2598,7 +3055,7 @@ Version 1.4.3");
                                                 plot_ui.line(aux_line__2);
                                             }
                                         }
-                                        if en_main.load(Ordering::Relaxed) {
+                                        if params.en_main.value() {
                                             if fill {
                                                 plot_ui.line(line.fill(0.0));
                                                 plot_ui.line(line_2.fill(0.0));
@@ -2607,7 +3064,7 @@ Version 1.4.3");
                                                 plot_ui.line(line_2);
                                             }
                                         }
-                                        if en_aux5.load(Ordering::Relaxed) {
+                                        if params.en_aux5.value() {
                                             if fill {
                                                 plot_ui.line(aux_line_5.fill(0.0));
                                                 plot_ui.line(aux_line_5_2.fill(0.0));
@@ -2621,10 +3078,13 @@ Version 1.4.3");
                                         // We shouldn't be here
                                     }
                                 }
+                                for rms_line in rms_lines {
+                                    plot_ui.line(rms_line);
+                                }
                                 // Draw our clipping guides if needed
                                 let clip_counter = is_clipping.load(Ordering::Relaxed);
                                 if clip_counter > 0.0 {
-                                    if stereo_view.load(Ordering::Relaxed) {
+                                    if params.stereo_view.value() {
                                         plot_ui.hline(
                                             HLine::new(2.0)
                                                 .color(egui::Color32::RED)
@@ -2656,10 +3116,337 @@ Version 1.4.3");
                                 }
                             })
                         .response;
+                        if scrubbing {
+                            // Dragging or scrolling right pulls older capture into view
+                            let points_per_pixel = samples.internal_length.load(Ordering::Acquire) as f32 / scope_response.rect.width();
+                            let mut delta = scope_response.drag_delta().x;
+                            if scope_response.hovered() {
+                                let scroll_delta = ui.input().scroll_delta;
+                                delta += scroll_delta.x + scroll_delta.y;
+                            }
+                            if flip {
+                                delta = -delta;
+                            }
+                            if delta != 0.0 {
+                                let offset = (samples.scrub_offset() as f32 + delta * points_per_pixel).max(0.0) as usize;
+                                samples.set_scrub_offset(offset);
+                                samples_2.set_scrub_offset(offset);
+                            }
+                            let seconds_back = samples.scrub_offset() as f32 * params.h_scale.value() as f32 / sample_rate.load(Ordering::Relaxed);
+                            ui.painter().text(
+                                scope_response.rect.left_top() + egui::vec2(8.0, 8.0),
+                                Align2::LEFT_TOP,
+                                format!("Hold -{:.2} s", seconds_back),
+                                FontId::monospace(12.0),
+                                guidelines
+                            );
+                        }
                     }
                 });
                 // Floating buttons
-                if !show_analyzer.load(Ordering::Relaxed) {
+                let spectral_view = params.show_analyzer.value() || params.show_spectrogram.value();
+                if !spectral_view && params.trigger_mode.value() != TriggerMode::Off {
+                    let mut trigger_ui = ui.child_ui(
+                        Rect { min: Pos2 { x: 10.0, y: 30.0 }, max: Pos2 { x: 730.0, y: 40.0 } },
+                        Layout::left_to_right(egui::Align::Center)
+                    );
+                    trigger_ui
+                        .scope(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.add(widgets::ParamSlider::for_param(&params.trigger_source, setter).with_width(40.0))
+                                    .on_hover_text("Channel the trigger listens to");
+                                ui.add(widgets::ParamSlider::for_param(&params.trigger_level, setter).with_width(40.0))
+                                    .on_hover_text("Level the signal has to cross");
+                                ui.add(widgets::ParamSlider::for_param(&params.trigger_slope, setter).with_width(40.0))
+                                    .on_hover_text("Trigger when crossing the level going up or going down");
+                                ui.add(widgets::ParamSlider::for_param(&params.trigger_holdoff, setter).with_width(40.0))
+                                    .on_hover_text("Time after a capture before the trigger arms again");
+                                ui.add(widgets::ParamSlider::for_param(&params.trigger_pre, setter).with_width(30.0))
+                                    .on_hover_text("How much of the window shows what came before the trigger");
+                                if params.trigger_mode.value() == TriggerMode::Single
+                                    && ui.button("Arm").on_hover_text("Take another single capture").clicked()
+                                {
+                                    trigger_rearm.store(true, Ordering::Relaxed);
+                                }
+                            });
+                        }).inner;
+                }
+                let analyzer_view = params.show_analyzer.value().then(|| params.analyzer_view.value());
+                if analyzer_view == Some(AnalyzerView::Spectrum) {
+                    let mut analyzer_ui = ui.child_ui(
+                        Rect { min: Pos2 { x: 10.0, y: 30.0 }, max: Pos2 { x: 730.0, y: 40.0 } },
+                        Layout::left_to_right(egui::Align::Center)
+                    );
+                    analyzer_ui
+                        .scope(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.add(widgets::ParamSlider::for_param(&params.fft_window, setter).with_width(80.0))
+                                    .on_hover_text("FFT window, Blackman-Harris keeps loud notes from burying quiet ones and Flat Top reads levels most accurately");
+                                ui.add(widgets::ParamSlider::for_param(&params.fft_overlap, setter).with_width(40.0))
+                                    .on_hover_text("How much each FFT frame overlaps the last, more overlap updates more often");
+                                ui.add(widgets::ParamSlider::for_param(&params.spectrum_averaging, setter).with_width(60.0))
+                                    .on_hover_text("Smooths the spectrum over successive FFT frames");
+                                ui.add(widgets::ParamSlider::for_param(&params.average_time, setter).with_width(50.0))
+                                    .on_hover_text("Averaging time constant");
+                                ui.add(slim_checkbox::ParamSlimCheckbox::new(&params.peak_hold, setter, "Peak Hold"));
+                                if params.peak_hold.value() {
+                                    ui.add(widgets::ParamSlider::for_param(&params.peak_hold_time, setter).with_width(50.0))
+                                        .on_hover_text("How long a peak stays put before it starts to fall");
+                                    ui.add(widgets::ParamSlider::for_param(&params.peak_release, setter).with_width(50.0))
+                                        .on_hover_text("How fast peaks fall once the hold time is up, 0 holds them until Peak Hold is switched off");
+                                }
+                            });
+                        }).inner;
+                    let mut tilt_ui = ui.child_ui(
+                        Rect { min: Pos2 { x: 10.0, y: 48.0 }, max: Pos2 { x: 730.0, y: 58.0 } },
+                        Layout::left_to_right(egui::Align::Center)
+                    );
+                    tilt_ui
+                        .scope(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.add(widgets::ParamSlider::for_param(&params.tilt_slope, setter).with_width(60.0))
+                                    .on_hover_text("Tilts the spectrum so a mix with a natural high end roll-off reads level");
+                                ui.add(widgets::ParamSlider::for_param(&params.tilt_pivot, setter).with_width(50.0))
+                                    .on_hover_text("Frequency the tilt turns around, it reads the same with any slope");
+                                egui::ComboBox::from_id_source("tilt_presets")
+                                    .selected_text("Tilt Presets")
+                                    .width(90.0)
+                                    .show_ui(ui, |ui| {
+                                        for (name, tilt, pivot) in TILT_PRESETS {
+                                            let current = params.tilt_slope.value() == tilt && params.tilt_pivot.value() == pivot;
+                                            if ui.selectable_label(current, name).clicked() {
+                                                setter.begin_set_parameter(&params.tilt_slope);
+                                                setter.set_parameter(&params.tilt_slope, tilt);
+                                                setter.end_set_parameter(&params.tilt_slope);
+                                                setter.begin_set_parameter(&params.tilt_pivot);
+                                                setter.set_parameter(&params.tilt_pivot, pivot);
+                                                setter.end_set_parameter(&params.tilt_pivot);
+                                            }
+                                        }
+                                    });
+                                ui.add_space(8.0);
+                                ui.add(widgets::ParamSlider::for_param(&params.analyzer_freq_min, setter).with_width(50.0))
+                                    .on_hover_text("Lowest frequency shown, drag or scroll on the analyzer to pan and zoom");
+                                ui.add(widgets::ParamSlider::for_param(&params.analyzer_freq_max, setter).with_width(50.0))
+                                    .on_hover_text("Highest frequency shown");
+                                ui.add(widgets::ParamSlider::for_param(&params.analyzer_db_min, setter).with_width(50.0))
+                                    .on_hover_text("Bottom of the dB range, hold Shift while scrolling to zoom it");
+                                ui.add(widgets::ParamSlider::for_param(&params.analyzer_db_max, setter).with_width(50.0))
+                                    .on_hover_text("Top of the dB range, double click the analyzer to reset both ranges");
+                                ui.add_space(8.0);
+                                if en_bar_mode.load(Ordering::Relaxed) {
+                                    ui.add(widgets::ParamSlider::for_param(&params.bar_bands, setter).with_width(50.0))
+                                        .on_hover_text("Bar Mode bands, fractional octaves on ISO centre frequencies or the old 64 even groups of bins");
+                                }
+                                ui.add(widgets::ParamSlider::for_param(&params.peak_labels, setter).with_width(50.0))
+                                    .on_hover_text("Labels the loudest peaks of each channel with their frequency, note and level");
+                            });
+                        }).inner;
+                }
+                if analyzer_view == Some(AnalyzerView::Spectrum) {
+                    // Reference spectra, snapshots of an input's averaged spectrum
+                    let mut references_open = analyzer.references_open;
+                    egui::Window::new("References")
+                        .open(&mut references_open)
+                        .collapsible(false)
+                        .resizable(false)
+                        .default_pos(Pos2::new(740.0, 70.0))
+                        .show(ui.ctx(), |ui| {
+                            let mut references = params.references.write().unwrap();
+                            ui.horizontal(|ui| {
+                                ui.label("Snapshot");
+                                for (name, magnitudes) in INPUT_NAMES.iter().zip(&analyzer.magnitudes) {
+                                    let snapshot = ui.add_enabled(!magnitudes.is_empty(), egui::Button::new(*name))
+                                        .on_hover_text("Freezes this input's averaged spectrum as a reference");
+                                    if snapshot.clicked() {
+                                        let bin_hz = sr.load(Ordering::Relaxed) / magnitudes.len() as f32;
+                                        let color = REFERENCE_COLORS[references.len() % REFERENCE_COLORS.len()];
+                                        let reference_name = format!("{} #{}", name, references.len() + 1);
+                                        references.push(ReferenceSpectrum::new(reference_name, color, magnitudes, bin_hz));
+                                    }
+                                }
+                            });
+                            let mut removed = None;
+                            for (index, reference) in references.iter_mut().enumerate() {
+                                ui.push_id(index, |ui| {
+                                    ui.horizontal(|ui| {
+                                        ui.checkbox(&mut reference.visible, "").on_hover_text("Show this reference");
+                                        let (swatch, _) = ui.allocate_exact_size(egui::vec2(12.0, 12.0), egui::Sense::hover());
+                                        let [r, g, b] = reference.color;
+                                        ui.painter().rect_filled(swatch, Rounding::none(), Color32::from_rgb(r, g, b));
+                                        ui.add(egui::TextEdit::singleline(&mut reference.name).desired_width(140.0));
+                                        if ui.small_button("x").on_hover_text("Delete this reference").clicked() {
+                                            removed = Some(index);
+                                        }
+                                    });
+                                });
+                            }
+                            if let Some(index) = removed {
+                                references.remove(index);
+                            }
+                            if references.is_empty() {
+                                ui.label("No references yet, they're saved with the project");
+                            }
+                        });
+                    analyzer.references_open = references_open;
+                    // Masking controls sit where the stereo switches are on the scope
+                    let mut masking_ui = ui.child_ui(
+                        Rect { min: Pos2 { x: 740.0, y: 30.0 }, max: Pos2 { x: 1040.0, y: 40.0 } },
+                        Layout::left_to_right(egui::Align::Center)
+                    );
+                    masking_ui
+                        .scope(|ui| {
+                            ui.horizontal(|ui| {
+                                channel_mode_menu(ui, &params, setter);
+                                ui.add(slim_checkbox::ParamSlimCheckbox::new(&params.masking, setter, "Masking"))
+                                    .on_hover_text("Shades where two inputs are both loud and close in level, with a collision score per band");
+                                if params.masking.value() {
+                                    ui.add(widgets::ParamSlider::for_param(&params.masking_a, setter).with_width(40.0))
+                                        .on_hover_text("First input to compare");
+                                    ui.add(widgets::ParamSlider::for_param(&params.masking_b, setter).with_width(40.0))
+                                        .on_hover_text("Second input to compare");
+                                }
+                            });
+                        }).inner;
+                    if params.masking.value() {
+                        let mut masking_range_ui = ui.child_ui(
+                            Rect { min: Pos2 { x: 740.0, y: 48.0 }, max: Pos2 { x: 1040.0, y: 58.0 } },
+                            Layout::left_to_right(egui::Align::Center)
+                        );
+                        masking_range_ui
+                            .scope(|ui| {
+                                ui.horizontal(|ui| {
+                                    ui.add(widgets::ParamSlider::for_param(&params.masking_threshold, setter).with_width(50.0))
+                                        .on_hover_text("Both inputs have to be louder than this to collide");
+                                    ui.add(widgets::ParamSlider::for_param(&params.masking_range, setter).with_width(50.0))
+                                        .on_hover_text("How close in level the two inputs have to be to collide");
+                                });
+                            }).inner;
+                    }
+                }
+                if analyzer_view == Some(AnalyzerView::Transfer) {
+                    let mut transfer_ui = ui.child_ui(
+                        Rect { min: Pos2 { x: 10.0, y: 30.0 }, max: Pos2 { x: 730.0, y: 40.0 } },
+                        Layout::left_to_right(egui::Align::Center)
+                    );
+                    transfer_ui
+                        .scope(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.add(widgets::ParamSlider::for_param(&params.transfer_reference, setter).with_width(40.0))
+                                    .on_hover_text("Input carrying the dry signal, main carries the processed one");
+                                ui.add(widgets::ParamSlider::for_param(&params.transfer_range, setter).with_width(50.0))
+                                    .on_hover_text("Gain shown above and below 0 dB");
+                                ui.add(widgets::ParamSlider::for_param(&params.fft_window, setter).with_width(80.0))
+                                    .on_hover_text("FFT window, Hann suits most measurements");
+                                ui.add(widgets::ParamSlider::for_param(&params.fft_overlap, setter).with_width(40.0))
+                                    .on_hover_text("How much each FFT frame overlaps the last, more overlap updates more often");
+                                ui.add(widgets::ParamSlider::for_param(&params.average_time, setter).with_width(50.0))
+                                    .on_hover_text("How long the measurement averages over, longer reads steadier with music as the test signal");
+                                channel_mode_menu(ui, &params, setter);
+                            });
+                        }).inner;
+                    let mut range_ui = ui.child_ui(
+                        Rect { min: Pos2 { x: 10.0, y: 48.0 }, max: Pos2 { x: 730.0, y: 58.0 } },
+                        Layout::left_to_right(egui::Align::Center)
+                    );
+                    range_ui
+                        .scope(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.add(widgets::ParamSlider::for_param(&params.analyzer_freq_min, setter).with_width(50.0))
+                                    .on_hover_text("Lowest frequency shown");
+                                ui.add(widgets::ParamSlider::for_param(&params.analyzer_freq_max, setter).with_width(50.0))
+                                    .on_hover_text("Highest frequency shown");
+                            });
+                        }).inner;
+                }
+                if analyzer_view == Some(AnalyzerView::Phase) {
+                    let mut phase_ui = ui.child_ui(
+                        Rect { min: Pos2 { x: 10.0, y: 30.0 }, max: Pos2 { x: 730.0, y: 40.0 } },
+                        Layout::left_to_right(egui::Align::Center)
+                    );
+                    phase_ui
+                        .scope(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.add(slim_checkbox::ParamSlimCheckbox::new(&params.phase_unwrap, setter, "Unwrap"))
+                                    .on_hover_text("Follows the phase across frequency instead of folding it into +/-180 degrees");
+                                ui.add(slim_checkbox::ParamSlimCheckbox::new(&params.phase_difference, setter, "Difference"))
+                                    .on_hover_text("Phase of main against a reference input, for lining up mics on the same source");
+                                if params.phase_difference.value() {
+                                    ui.add(widgets::ParamSlider::for_param(&params.transfer_reference, setter).with_width(40.0))
+                                        .on_hover_text("Input main is compared against");
+                                    ui.add(widgets::ParamSlider::for_param(&params.average_time, setter).with_width(50.0))
+                                        .on_hover_text("How long the difference averages over");
+                                }
+                                ui.add(widgets::ParamSlider::for_param(&params.fft_window, setter).with_width(80.0))
+                                    .on_hover_text("FFT window, Hann suits most measurements");
+                                ui.add(widgets::ParamSlider::for_param(&params.fft_overlap, setter).with_width(40.0))
+                                    .on_hover_text("How much each FFT frame overlaps the last, more overlap updates more often");
+                                channel_mode_menu(ui, &params, setter);
+                            });
+                        }).inner;
+                    let mut range_ui = ui.child_ui(
+                        Rect { min: Pos2 { x: 10.0, y: 48.0 }, max: Pos2 { x: 730.0, y: 58.0 } },
+                        Layout::left_to_right(egui::Align::Center)
+                    );
+                    range_ui
+                        .scope(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.add(widgets::ParamSlider::for_param(&params.analyzer_freq_min, setter).with_width(50.0))
+                                    .on_hover_text("Lowest frequency shown");
+                                ui.add(widgets::ParamSlider::for_param(&params.analyzer_freq_max, setter).with_width(50.0))
+                                    .on_hover_text("Highest frequency shown");
+                                if !params.phase_difference.value() {
+                                    ui.add(widgets::ParamSlider::for_param(&params.analyzer_db_min, setter).with_width(50.0))
+                                        .on_hover_text("Bins quieter than this have no phase worth showing and are left out");
+                                }
+                            });
+                        }).inner;
+                }
+                if params.show_spectrogram.value() {
+                    let mut spectrogram_ui = ui.child_ui(
+                        Rect { min: Pos2 { x: 10.0, y: 30.0 }, max: Pos2 { x: 730.0, y: 40.0 } },
+                        Layout::left_to_right(egui::Align::Center)
+                    );
+                    spectrogram_ui
+                        .scope(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.add(widgets::ParamSlider::for_param(&params.spectrogram_source, setter).with_width(40.0))
+                                    .on_hover_text("Input shown in the spectrogram");
+                                ui.add(widgets::ParamSlider::for_param(&params.spectrogram_colormap, setter).with_width(60.0))
+                                    .on_hover_text("Colors the dB range runs through, quietest first");
+                                ui.add(widgets::ParamSlider::for_param(&params.spectrogram_history, setter).with_width(50.0))
+                                    .on_hover_text("How much time the spectrogram spans");
+                                ui.add(widgets::ParamSlider::for_param(&params.fft_window, setter).with_width(80.0))
+                                    .on_hover_text("FFT window, Blackman-Harris keeps loud notes from burying quiet ones and Flat Top reads levels most accurately");
+                                ui.add(widgets::ParamSlider::for_param(&params.fft_overlap, setter).with_width(40.0))
+                                    .on_hover_text("More overlap gives the spectrogram more columns per second");
+                                channel_mode_menu(ui, &params, setter);
+                            });
+                        }).inner;
+                    let mut range_ui = ui.child_ui(
+                        Rect { min: Pos2 { x: 10.0, y: 48.0 }, max: Pos2 { x: 730.0, y: 58.0 } },
+                        Layout::left_to_right(egui::Align::Center)
+                    );
+                    range_ui
+                        .scope(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.add(widgets::ParamSlider::for_param(&params.tilt_slope, setter).with_width(60.0))
+                                    .on_hover_text("Tilts the spectrum so a mix with a natural high end roll-off reads level");
+                                ui.add(widgets::ParamSlider::for_param(&params.tilt_pivot, setter).with_width(50.0))
+                                    .on_hover_text("Frequency the tilt turns around, it reads the same with any slope");
+                                ui.add_space(8.0);
+                                ui.add(widgets::ParamSlider::for_param(&params.analyzer_freq_min, setter).with_width(50.0))
+                                    .on_hover_text("Lowest frequency shown");
+                                ui.add(widgets::ParamSlider::for_param(&params.analyzer_freq_max, setter).with_width(50.0))
+                                    .on_hover_text("Highest frequency shown");
+                                ui.add(widgets::ParamSlider::for_param(&params.analyzer_db_min, setter).with_width(50.0))
+                                    .on_hover_text("Level drawn in the colormap's first color");
+                                ui.add(widgets::ParamSlider::for_param(&params.analyzer_db_max, setter).with_width(50.0))
+                                    .on_hover_text("Level drawn in the colormap's last color");
+                            });
+                        }).inner;
+                }
+                if !spectral_view {
                     // Gated by nih_plug update
                     let mut stereo_switch_ui = ui.child_ui(
                         Rect { min: Pos2 { x: 740.0, y: 30.0 },max: Pos2 { x: 1040.0, y: 40.0 } },
@@ -2668,22 +3455,22 @@ Version 1.4.3");
                     stereo_switch_ui
                         .scope(|ui| {
                             ui.horizontal(|ui|{
-                                let checkstereo = slim_checkbox::AtomicSlimCheckbox::new(&stereo_view, "Stereo View");
+                                let checkstereo = slim_checkbox::ParamSlimCheckbox::new(&params.stereo_view, setter, "Stereo View");
                                 ui.add(checkstereo);
-                                let leftchannel = slim_checkbox::AtomicSlimCheckbox::new(&en_left_channel, "Left Channel");
+                                let leftchannel = slim_checkbox::ParamSlimCheckbox::new(&params.en_left_channel, setter, "Left Channel");
                                 ui.add(leftchannel);
-                                let rightchanel = slim_checkbox::AtomicSlimCheckbox::new(&en_right_channel, "Right Channel");
+                                let rightchanel = slim_checkbox::ParamSlimCheckbox::new(&params.en_right_channel, setter, "Right Channel");
                                 ui.add(rightchanel);
                             });
                         }).inner;
                     /*ui.scope_builder(
                         egui::UiBuilder::new().max_rect(Rect { min: Pos2 { x: 740.0, y: 30.0 }, max: Pos2 { x: 1040.0, y: 40.0 } }),
                         |ui|ui.horizontal(|ui|{
-                                let checkstereo = slim_checkbox::AtomicSlimCheckbox::new(&stereo_view, "Stereo View");
+                                let checkstereo = slim_checkbox::ParamSlimCheckbox::new(&params.stereo_view, setter, "Stereo View");
                                 ui.add(checkstereo);
-                                let leftchannel = slim_checkbox::AtomicSlimCheckbox::new(&en_left_channel, "Left Channel");
+                                let leftchannel = slim_checkbox::ParamSlimCheckbox::new(&params.en_left_channel, setter, "Left Channel");
                                 ui.add(leftchannel);
-                                let rightchanel = slim_checkbox::AtomicSlimCheckbox::new(&en_right_channel, "Right Channel");
+                                let rightchanel = slim_checkbox::ParamSlimCheckbox::new(&params.en_right_channel, setter, "Right Channel");
                                 ui.add(rightchanel);
                             })
                     );*/
@@ -2692,3 +3479,175 @@ Version 1.4.3");
         },
     )
 }
+
+// Keeps a min/max pair inside limits, moving it back in before shrinking it and never letting it
+// get narrower than min_span
+fn clamp_span(low: f32, high: f32, limits: (f32, f32), min_span: f32) -> (f32, f32) {
+    let span = (high - low).clamp(min_span, limits.1 - limits.0);
+    let low = low.clamp(limits.0, limits.1 - span);
+    (low, low + span)
+}
+
+// Drop down of the analyzer channel mode of every input, a row of modes per input
+fn channel_mode_menu(ui: &mut egui::Ui, params: &ScrollscopeParams, setter: &ParamSetter) {
+    egui::ComboBox::from_id_source("channel_modes")
+        .selected_text("Channels")
+        .width(80.0)
+        .show_ui(ui, |ui| {
+            for (name, mode) in INPUT_NAMES.iter().zip(params.channel_modes()) {
+                ui.horizontal(|ui| {
+                    ui.add_sized([40.0, 14.0], egui::Label::new(*name));
+                    for (index, variant) in ChannelMode::variants().iter().enumerate() {
+                        if ui.selectable_label(mode.value().to_index() == index, *variant).clicked() {
+                            setter.begin_set_parameter(mode);
+                            setter.set_parameter(mode, ChannelMode::from_index(index));
+                            setter.end_set_parameter(mode);
+                        }
+                    }
+                });
+            }
+        })
+        .response
+        .on_hover_text("Left, right, their sum, mid or side of each input");
+}
+
+// Crosshair readout box, a line of text per entry. Kept inside bounds by flipping it to the other
+// side of the pointer near the edges
+fn draw_readout(painter: &egui::Painter, pointer: Pos2, bounds: Rect, lines: Vec<(String, Color32)>, background_color: Color32) {
+    let galleys: Vec<_> = lines
+        .into_iter()
+        .map(|(text, color)| painter.layout_no_wrap(text, FontId::monospace(12.0), color))
+        .collect();
+    let size = egui::vec2(
+        galleys.iter().map(|galley| galley.size().x).fold(0.0, f32::max) + 8.0,
+        galleys.iter().map(|galley| galley.size().y).sum::<f32>() + 8.0
+    );
+    let mut corner = pointer + egui::vec2(12.0, 12.0);
+    if corner.x + size.x > bounds.right() {
+        corner.x = pointer.x - 12.0 - size.x;
+    }
+    if corner.y + size.y > bounds.bottom() {
+        corner.y = pointer.y - 12.0 - size.y;
+    }
+    painter.rect_filled(Rect::from_min_size(corner, size), Rounding::none(), background_color.linear_multiply(0.85));
+    let mut line_pos = corner + egui::vec2(4.0, 4.0);
+    for galley in galleys {
+        let height = galley.size().y;
+        painter.galley(line_pos, galley);
+        line_pos.y += height;
+    }
+}
+
+// Splits points into lines, breaking wherever a point is None or the line jumps further than max_jump
+fn trace_runs(points: impl Iterator<Item = Option<Pos2>>, max_jump: f32) -> Vec<Vec<Pos2>> {
+    let mut runs: Vec<Vec<Pos2>> = vec![Vec::new()];
+    for point in points {
+        let Some(point) = point else {
+            runs.push(Vec::new());
+            continue;
+        };
+        if runs.last().and_then(|run| run.last()).is_some_and(|last| (last.y - point.y).abs() > max_jump) {
+            runs.push(Vec::new());
+        }
+        if let Some(run) = runs.last_mut() {
+            run.push(point);
+        }
+    }
+    runs.retain(|run| run.len() > 1);
+    runs
+}
+
+// Labelled frequency guidelines for views with frequency across, lines go in shapes under the plot
+fn frequency_guidelines(painter: &egui::Painter, shapes: &mut Vec<egui::Shape>, freq_scaler: f32, x_shift: f32) {
+    let freqs: [f32; 12] = [
+        0.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0, 1000.0, 2000.0, 5000.0, 10000.0, 18000.0];
+    let scaled_ref_freqs: Vec<f32> = freqs.iter().map(|num|{num.log10() * freq_scaler}).collect();
+    for (num,scaled_num) in freqs.iter().zip(scaled_ref_freqs.iter()) {
+        shapes.push(
+            epaint::Shape::line_segment(
+                [
+                    Pos2::new(*scaled_num + x_shift, 515.0),
+                    Pos2::new(*scaled_num + x_shift, 30.0)
+                ],
+                Stroke::new(0.5, Color32::GRAY)
+            )
+        );
+        painter.text(
+            Pos2::new(scaled_num + 2.0 + x_shift, 510.0), 
+            Align2::LEFT_CENTER, 
+            *num, 
+            FontId::monospace(12.0), 
+            Color32::GRAY
+        );
+    }
+    let sub_freqs: [f32; 18] = [
+        30.0,40.0,60.0,70.0,80.0,90.0,300.0,400.0,600.0,700.0,800.0,900.0,3000.0,4000.0,6000.0,7000.0,8000.0,9000.0];
+    let scaled_sub_freqs: Vec<f32> = sub_freqs.iter().map(|num|{num.log10() * freq_scaler}).collect();
+    for scaled_num in scaled_sub_freqs.iter() {
+        shapes.push(
+            epaint::Shape::line_segment(
+                [
+                    Pos2::new(*scaled_num + x_shift, 515.0),
+                    Pos2::new(*scaled_num + x_shift, 30.0)
+                ],
+                Stroke::new(0.5, Color32::DARK_GRAY)
+            )
+        );
+    }
+}
+
+// Oscilloscope points for one channel. When decimating by envelope each x gets both the bucket
+// max and min so the line sweeps the whole band instead of missing the peaks
+fn scope_points(buffer: &OptimizedBuffer, channel: usize, envelope: bool, flip: bool, enabled: bool, offset: f64) -> PlotPoints {
+    let highs = buffer.get_samples(channel);
+    let buffer_len = highs.len();
+    let x_pos = |i: usize| if flip { (buffer_len - i) as f64 } else { i as f64 };
+    if !enabled {
+        return (0..buffer_len).map(|i| [x_pos(i), 0.0]).collect();
+    }
+    if !envelope {
+        return highs.iter().enumerate().map(|(i, high)| [x_pos(i), *high as f64 + offset]).collect();
+    }
+    let lows = buffer.get_low_samples(channel);
+    highs
+        .iter()
+        .zip(lows.iter())
+        .enumerate()
+        .flat_map(|(i, (high, low))| {
+            let x = x_pos(i);
+            [[x, *high as f64 + offset], [x, *low as f64 + offset]]
+        })
+        .collect()
+}
+
+// Long view points for one channel, always a min/max band since each point covers many samples
+fn long_points(store: &EnvelopeStore, channel: usize, window_samples: usize, flip: bool, enabled: bool, offset: f64) -> PlotPoints {
+    let (lows, highs) = store.get_envelope(channel, window_samples, LONG_VIEW_POINTS);
+    let length = highs.len();
+    let x_pos = |i: usize| if flip { (length - i) as f64 } else { i as f64 };
+    if !enabled {
+        return (0..length).map(|i| [x_pos(i), 0.0]).collect();
+    }
+    highs
+        .iter()
+        .zip(lows.iter())
+        .enumerate()
+        .flat_map(|(i, (high, low))| {
+            let x = x_pos(i);
+            [[x, *high as f64 + offset], [x, *low as f64 + offset]]
+        })
+        .collect()
+}
+
+// The RMS body of a channel drawn the same way as the envelope band, +/- RMS around the center
+fn rms_points(buffer: &OptimizedBuffer, channel: usize, flip: bool, offset: f64) -> PlotPoints {
+    let rms = buffer.get_rms_samples(channel);
+    let buffer_len = rms.len();
+    rms.iter()
+        .enumerate()
+        .flat_map(|(i, level)| {
+            let x = if flip { (buffer_len - i) as f64 } else { i as f64 };
+            [[x, *level as f64 + offset], [x, -*level as f64 + offset]]
+        })
+        .collect()
+}
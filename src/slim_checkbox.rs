@@ -1,9 +1,10 @@
 // This is a copy of egui checkbox just with slimmer padding/spacing needed in Scrollscope
-// I also added a version to work with Atomic structures
+// I also added versions to work with Atomic structures and nih-plug BoolParams
 
 // ----------------------------------------------------------------------------
 
 use std::sync::atomic::{AtomicBool, Ordering};
+use nih_plug::prelude::{BoolParam, ParamSetter};
 use nih_plug_egui::egui::{
     epaint, pos2, vec2, NumExt, Response, Rounding, Sense, Shape, TextStyle, Ui, Vec2, Widget, WidgetInfo, WidgetText, WidgetType
 };
@@ -42,91 +43,129 @@ impl<'a> AtomicSlimCheckbox<'a> {
     }
 }
 
+#[must_use = "You should put this widget in an ui with `ui.add(widget);`"]
+pub struct ParamSlimCheckbox<'a> {
+    param: &'a BoolParam,
+    setter: &'a ParamSetter<'a>,
+    text: WidgetText,
+}
+
+impl<'a> ParamSlimCheckbox<'a> {
+    pub fn new(param: &'a BoolParam, setter: &'a ParamSetter<'a>, text: impl Into<WidgetText>) -> Self {
+        ParamSlimCheckbox {
+            param,
+            setter,
+            text: text.into(),
+        }
+    }
+}
+
+impl<'a> Widget for ParamSlimCheckbox<'a> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let ParamSlimCheckbox { param, setter, text } = self;
+        let checked = param.value();
+        // A click is a whole gesture so the host records it as one automation point
+        toggle_ui(ui, text, checked, || {
+            setter.begin_set_parameter(param);
+            setter.set_parameter(param, !checked);
+            setter.end_set_parameter(param);
+        })
+    }
+}
+
 impl<'a> Widget for AtomicSlimCheckbox<'a> {
     fn ui(self, ui: &mut Ui) -> Response {
         let AtomicSlimCheckbox { checked, text } = self;
+        toggle_ui(ui, text, checked.load(Ordering::SeqCst), || {
+            checked.fetch_xor(true, Ordering::SeqCst);
+        })
+    }
+}
 
-        let spacing = &ui.spacing();
-        let icon_width = spacing.icon_width;
-        let icon_spacing = spacing.icon_spacing;
+// Layout and painting shared by the atomic and param checkboxes. The value can take a moment to
+// come back from the host for params, so this paints what the box will be after a click
+fn toggle_ui(ui: &mut Ui, text: WidgetText, mut checked: bool, toggle: impl FnOnce()) -> Response {
+    let spacing = &ui.spacing();
+    let icon_width = spacing.icon_width;
+    let icon_spacing = spacing.icon_spacing;
 
-        let (text, mut desired_size) = if text.is_empty() {
-            (None, vec2(icon_width, 0.0))
-        } else {
-            let total_extra = vec2(icon_width + icon_spacing, 0.0);
+    let (text, mut desired_size) = if text.is_empty() {
+        (None, vec2(icon_width, 0.0))
+    } else {
+        let total_extra = vec2(icon_width + icon_spacing, 0.0);
 
-            let wrap_width = ui.available_width() - total_extra.x;
-            let text = text.into_galley(ui, None, wrap_width, TextStyle::Button);
+        let wrap_width = ui.available_width() - total_extra.x;
+        let text = text.into_galley(ui, None, wrap_width, TextStyle::Button);
 
-            let mut desired_size = total_extra + text.size();
+        let mut desired_size = total_extra + text.size();
 
-            /////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
-            //This is the only piece I changed -Ardura
-            desired_size =
-                desired_size.at_least(vec2(spacing.interact_size.x * 0.45, spacing.interact_size.y));
-            /////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+        /////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+        //This is the only piece I changed -Ardura
+        desired_size =
+            desired_size.at_least(vec2(spacing.interact_size.x * 0.45, spacing.interact_size.y));
+        /////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
-            (Some(text), desired_size)
-        };
+        (Some(text), desired_size)
+    };
 
-        desired_size = desired_size.at_least(Vec2::splat(spacing.interact_size.y));
-        desired_size.y = desired_size.y.max(icon_width);
-        let (rect, mut response) = ui.allocate_exact_size(desired_size, Sense::click());
+    desired_size = desired_size.at_least(Vec2::splat(spacing.interact_size.y));
+    desired_size.y = desired_size.y.max(icon_width);
+    let (rect, mut response) = ui.allocate_exact_size(desired_size, Sense::click());
 
-        if response.clicked() {
-            checked.fetch_xor(true, Ordering::SeqCst);
-            response.mark_changed();
-        }
-        response.widget_info(|| {
-            WidgetInfo::selected(
-                WidgetType::Checkbox,
-                //true,
-                checked.load(Ordering::SeqCst),
-                text.as_ref().map_or("", |x| x.text()),
-            )
-        });
+    if response.clicked() {
+        toggle();
+        checked = !checked;
+        response.mark_changed();
+    }
+    response.widget_info(|| {
+        WidgetInfo::selected(
+            WidgetType::Checkbox,
+            //true,
+            checked,
+            text.as_ref().map_or("", |x| x.text()),
+        )
+    });
 
-        if ui.is_rect_visible(rect) {
-            // let visuals = ui.style().interact_selectable(&response, *checked); // too colorful
-            let visuals = ui.style().interact(&response);
-            let (small_icon_rect, big_icon_rect) = ui.spacing().icon_rectangles(rect);
-            ui.painter().add(epaint::RectShape {
-                rect: big_icon_rect.expand(visuals.expansion),
-                //corner_radius: visuals.corner_radius,
-                rounding: Rounding::none(),
-                fill: visuals.bg_fill,
-                stroke: visuals.bg_stroke,
-                //fill_texture_id: TextureId::default(),
-                //uv: big_icon_rect.expand(visuals.expansion),
-                //blur_width: 0.0,
-                //stroke_kind: epaint::StrokeKind::Inside,
-                //round_to_pixels: Option::None,
-                //brush: Option::None,
-            });
+    if ui.is_rect_visible(rect) {
+        // let visuals = ui.style().interact_selectable(&response, *checked); // too colorful
+        let visuals = ui.style().interact(&response);
+        let (small_icon_rect, big_icon_rect) = ui.spacing().icon_rectangles(rect);
+        ui.painter().add(epaint::RectShape {
+            rect: big_icon_rect.expand(visuals.expansion),
+            //corner_radius: visuals.corner_radius,
+            rounding: Rounding::none(),
+            fill: visuals.bg_fill,
+            stroke: visuals.bg_stroke,
+            //fill_texture_id: TextureId::default(),
+            //uv: big_icon_rect.expand(visuals.expansion),
+            //blur_width: 0.0,
+            //stroke_kind: epaint::StrokeKind::Inside,
+            //round_to_pixels: Option::None,
+            //brush: Option::None,
+        });
 
-            if checked.load(Ordering::SeqCst) {
-                // Check mark:
-                ui.painter().add(Shape::line(
-                    vec![
-                        pos2(small_icon_rect.left(), small_icon_rect.center().y),
-                        pos2(small_icon_rect.center().x, small_icon_rect.bottom()),
-                        pos2(small_icon_rect.right(), small_icon_rect.top()),
-                    ],
-                    visuals.fg_stroke,
-                ));
-            }
-            if let Some(text) = text {
-                let text_pos = pos2(
-                    rect.min.x + icon_width + icon_spacing,
-                    rect.center().y - 0.5 * text.size().y,
-                );
-                //ui.painter().galley(text_pos, text, visuals.fg_stroke.color);
-                text.paint_with_visuals(ui.painter(), text_pos, visuals);
-            }
+        if checked {
+            // Check mark:
+            ui.painter().add(Shape::line(
+                vec![
+                    pos2(small_icon_rect.left(), small_icon_rect.center().y),
+                    pos2(small_icon_rect.center().x, small_icon_rect.bottom()),
+                    pos2(small_icon_rect.right(), small_icon_rect.top()),
+                ],
+                visuals.fg_stroke,
+            ));
+        }
+        if let Some(text) = text {
+            let text_pos = pos2(
+                rect.min.x + icon_width + icon_spacing,
+                rect.center().y - 0.5 * text.size().y,
+            );
+            //ui.painter().galley(text_pos, text, visuals.fg_stroke.color);
+            text.paint_with_visuals(ui.painter(), text_pos, visuals);
         }
-
-        response
     }
+
+    response
 }
 
 // This is here but I don't use it in scrollscope